@@ -0,0 +1,521 @@
+//! Text protocol for using the engine as an analysis backend.
+//!
+//! Commands:
+//! * `position startpos|<board> [moves <move>...]`
+//! * `go [movetime <ms>] [nodes <n>] [infinite]`
+//! * `stop`
+//! * `setoption name <name> value <value>`: see `Settings`
+//! * `isready`: replies `readyok`
+//! * `quit`
+//!
+//! `go` searches in the background and reports:
+//! * `info solutions <n> all|partial`
+//! * `info move <move> solutions <n> value win|loss nodes <n>`: for each solved move
+//! * `info move <move> time <ms> nodes <n>`: the best move so far, after each midgame iteration
+//! * `info verdict win|loss|unknown nodes <n> time <ms>`: for the side to move
+//! * `bestmove <move>`
+//!
+//! Errors are reported as `info string <message>`.
+//!
+//! Before all solutions are generated, the midgame strategy is run repeatedly, doubling its time
+//! budget up to `game_time_limit`. Its endgame searches count towards `nodes`.
+
+use crate::{
+    board::{Board, FullMove, Move},
+    endgame::{EndgameResult, EndgameSolver},
+    error::InvalidInput,
+    settings::{self, Settings},
+    solution_table::SolutionTable,
+    strategy::{MidgameStrategy, SearchContext, Strategies},
+    symmetry::Symmetry,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, BufRead, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+pub enum AnalysisCommand {
+    Position { board: Board, moves: Vec<Move> },
+    Go(SearchLimits),
+    Stop,
+    SetOption { name: String, value: String },
+    IsReady,
+    Quit,
+}
+
+/// No limits means search until solved or stopped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SearchLimits {
+    pub movetime: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+impl FromStr for AnalysisCommand {
+    type Err = InvalidInput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let mut tokens = s.split_whitespace();
+        let command = match tokens.next().ok_or(InvalidInput)? {
+            "position" => {
+                let board = match tokens.next().ok_or(InvalidInput)? {
+                    "startpos" => Board::new(),
+                    board => board.parse()?,
+                };
+                let mut moves = Vec::new();
+                match tokens.next() {
+                    None => {}
+                    Some("moves") => {
+                        for mov in tokens.by_ref() {
+                            moves.push(mov.parse()?);
+                        }
+                    }
+                    Some(_) => return Err(InvalidInput),
+                }
+                Self::Position { board, moves }
+            }
+            "go" => {
+                let mut limits = SearchLimits {
+                    movetime: None,
+                    nodes: None,
+                };
+                while let Some(token) = tokens.next() {
+                    match token {
+                        "movetime" => {
+                            let ms = parse_number(tokens.next())?;
+                            limits.movetime = Some(Duration::from_millis(ms));
+                        }
+                        "nodes" => limits.nodes = Some(parse_number(tokens.next())?),
+                        "infinite" => {}
+                        _ => return Err(InvalidInput),
+                    }
+                }
+                Self::Go(limits)
+            }
+            "stop" => Self::Stop,
+            "setoption" => {
+                if tokens.next() != Some("name") {
+                    return Err(InvalidInput);
+                }
+                let name = tokens.next().ok_or(InvalidInput)?.to_string();
+                if tokens.next() != Some("value") {
+                    return Err(InvalidInput);
+                }
+                let value = tokens.next().ok_or(InvalidInput)?.to_string();
+                Self::SetOption { name, value }
+            }
+            "isready" => Self::IsReady,
+            "quit" => Self::Quit,
+            _ => return Err(InvalidInput),
+        };
+        if tokens.next().is_some() {
+            return Err(InvalidInput);
+        }
+        Ok(command)
+    }
+}
+
+fn parse_number(token: Option<&str>) -> Result<u64, InvalidInput> {
+    token.ok_or(InvalidInput)?.parse().map_err(|_| InvalidInput)
+}
+
+pub fn run_analysis_interaction(settings: Settings, strategies: Strategies) -> io::Result<()> {
+    let output: Arc<Mutex<dyn Write + Send>> = Arc::new(Mutex::new(io::stdout()));
+    run_analysis(settings, strategies, io::stdin().lock(), output)
+}
+
+/// Runs the protocol until `quit` or the end of input. At the end of input, waits for the search
+/// to finish.
+pub fn run_analysis(
+    settings: Settings,
+    strategies: Strategies,
+    input: impl BufRead,
+    output: Arc<Mutex<dyn Write + Send>>,
+) -> io::Result<()> {
+    let mut board = Board::new();
+    // None while searching.
+    let mut engine = Some(Engine {
+        context: SearchContext::new(settings),
+        strategies,
+    });
+    let mut search: Option<Search> = None;
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let command = match line.parse() {
+            Ok(command) => command,
+            Err(InvalidInput) => {
+                write_line(&output, format_args!("info string invalid command: {line}"))?;
+                continue;
+            }
+        };
+        match command {
+            AnalysisCommand::Position {
+                board: new_board,
+                moves,
+            } => {
+                stop_search(&mut search, &mut engine)?;
+                let mut new_board = new_board;
+                match moves.iter().try_for_each(|&mov| new_board.make_move(mov)) {
+                    Ok(()) => board = new_board,
+                    Err(InvalidInput) => {
+                        write_line(&output, format_args!("info string invalid move"))?;
+                    }
+                }
+            }
+            AnalysisCommand::Go(limits) => {
+                stop_search(&mut search, &mut engine)?;
+                search = Some(Search::start(
+                    engine.take().unwrap(),
+                    board,
+                    limits,
+                    output.clone(),
+                ));
+            }
+            AnalysisCommand::Stop => {
+                stop_search(&mut search, &mut engine)?;
+            }
+            AnalysisCommand::SetOption { name, value } => {
+                let engine = stop_search(&mut search, &mut engine)?;
+                let context = &mut engine.context;
+                let memory = context.settings.transposition_table_memory;
+                let strategies = context.settings.strategies;
+                match context.settings.set(&name, &value) {
+                    Ok(()) => {
+                        let new_memory = context.settings.transposition_table_memory;
+                        if new_memory != memory {
+                            context.endgame_solver = EndgameSolver::new(new_memory);
+                        }
+                        if context.settings.strategies != strategies {
+                            engine.strategies = Strategies::from(context.settings.strategies);
+                        }
+                    }
+                    Err(InvalidInput) => {
+                        write_line(&output, format_args!("info string invalid option: {name}"))?;
+                    }
+                }
+            }
+            AnalysisCommand::IsReady => write_line(&output, format_args!("readyok"))?,
+            AnalysisCommand::Quit => {
+                stop_search(&mut search, &mut engine)?;
+                return Ok(());
+            }
+        }
+    }
+    if let Some(search) = search.take() {
+        search.join()?;
+    }
+    Ok(())
+}
+
+/// State that the search takes while it runs.
+struct Engine {
+    context: SearchContext,
+    strategies: Strategies,
+}
+
+/// Stops the search if there is one, and returns the engine.
+fn stop_search<'a>(
+    search: &mut Option<Search>,
+    engine: &'a mut Option<Engine>,
+) -> io::Result<&'a mut Engine> {
+    if let Some(search) = search.take() {
+        *engine = Some(search.stop()?);
+    }
+    Ok(engine.as_mut().unwrap())
+}
+
+fn write_line(output: &Mutex<dyn Write + Send>, line: fmt::Arguments) -> io::Result<()> {
+    let mut output = output.lock().unwrap();
+    writeln!(output, "{line}")?;
+    output.flush()
+}
+
+/// A search running in the background.
+struct Search {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<(Engine, io::Result<()>)>,
+}
+
+impl Search {
+    fn start(
+        mut engine: Engine,
+        board: Board,
+        limits: SearchLimits,
+        output: Arc<Mutex<dyn Write + Send>>,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        if let Some(movetime) = limits.movetime {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                thread::sleep(movetime);
+                stop.store(true, Ordering::Relaxed);
+            });
+        }
+        let thread = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let res = analyze(&mut engine, board, limits, &stop, &*output);
+                (engine, res)
+            })
+        };
+        Self { stop, thread }
+    }
+
+    fn stop(self) -> io::Result<Engine> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.join()
+    }
+
+    fn join(self) -> io::Result<Engine> {
+        let (engine, res) = self.thread.join().unwrap();
+        res?;
+        Ok(engine)
+    }
+}
+
+/// Time budget of the first midgame iteration.
+const MIDGAME_FIRST_TIME_BUDGET: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Verdict {
+    Win,
+    Loss,
+    Unknown,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Win => write!(f, "win"),
+            Self::Loss => write!(f, "loss"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+fn analyze(
+    engine: &mut Engine,
+    board: Board,
+    limits: SearchLimits,
+    stop: &Arc<AtomicBool>,
+    output: &Mutex<dyn Write + Send>,
+) -> io::Result<()> {
+    let start_time = Instant::now();
+    let context = &mut engine.context;
+    // Analyze in the frame of the normalized board, so that results carry over between
+    // symmetric positions.
+    let (board, symmetry) = context.normalize(&board);
+    let (res, solutions) = SolutionTable::generate_until_stopped(
+        &board,
+        settings::SOLUTIONS_MIN,
+        context.settings.solutions_max,
+        stop,
+        context.solution_ids(),
+    );
+    let all_solutions_generated = res.is_ok();
+    write_line(
+        output,
+        format_args!(
+            "info solutions {} {}",
+            solutions.len(),
+            if all_solutions_generated {
+                "all"
+            } else {
+                "partial"
+            }
+        ),
+    )?;
+
+    let (verdict, nodes, mov) = if all_solutions_generated {
+        analyze_endgame(context, &solutions, &symmetry, limits.nodes, stop, output)?
+    } else {
+        let (nodes, mov) = analyze_midgame(
+            context,
+            &mut *engine.strategies.midgame,
+            &board,
+            &solutions,
+            &symmetry,
+            limits,
+            start_time,
+            stop,
+            output,
+        )?;
+        (Verdict::Unknown, nodes, mov)
+    };
+    write_line(
+        output,
+        format_args!(
+            "info verdict {verdict} nodes {nodes} time {}",
+            start_time.elapsed().as_millis()
+        ),
+    )?;
+    write_line(
+        output,
+        format_args!("bestmove {}", symmetry.backward_full_move(mov)),
+    )
+}
+
+/// Runs the midgame strategy with doubling time budgets, and reports the move after each
+/// iteration. An iteration cut short by `stop` or `nodes` doesn't replace the previous move.
+#[allow(clippy::too_many_arguments)]
+fn analyze_midgame(
+    context: &mut SearchContext,
+    midgame: &mut dyn MidgameStrategy,
+    board: &Board,
+    solutions: &SolutionTable,
+    symmetry: &Symmetry,
+    limits: SearchLimits,
+    start_time: Instant,
+    stop: &Arc<AtomicBool>,
+    output: &Mutex<dyn Write + Send>,
+) -> io::Result<(u64, FullMove)> {
+    let start_nodes = context.endgame_solver.total_nodes();
+    let nodes_used = |context: &SearchContext| context.endgame_solver.total_nodes() - start_nodes;
+    let is_stopped = |nodes: u64| {
+        let limit_reached = match limits.nodes {
+            Some(node_limit) => nodes >= node_limit,
+            None => false,
+        };
+        limit_reached || stop.load(Ordering::Relaxed)
+    };
+    context.endgame_solver.set_stop_flag(stop.clone());
+    context.endgame_solver.set_node_limit(limits.nodes);
+
+    let mut best_move = None;
+    let mut time_budget = MIDGAME_FIRST_TIME_BUDGET;
+    loop {
+        let time_left = match limits.movetime {
+            Some(movetime) => time_budget.min(movetime.saturating_sub(start_time.elapsed())),
+            None => time_budget,
+        };
+        let mut iteration_board = *board;
+        let decision = midgame.choose_move(
+            context,
+            &mut iteration_board,
+            solutions,
+            Instant::now(),
+            time_left,
+        );
+        let nodes = nodes_used(context);
+        if best_move.is_some() && is_stopped(nodes) {
+            break;
+        }
+        best_move = Some(decision.mov);
+        write_line(
+            output,
+            format_args!(
+                "info move {} time {} nodes {nodes}",
+                symmetry.backward_full_move(decision.mov),
+                start_time.elapsed().as_millis()
+            ),
+        )?;
+        if is_stopped(nodes) || time_budget >= context.settings.game_time_limit {
+            break;
+        }
+        time_budget *= 2;
+    }
+    context.endgame_solver.clear_stop_flag();
+    context.endgame_solver.set_node_limit(None);
+    Ok((nodes_used(context), best_move.unwrap()))
+}
+
+/// Solves the moves in order, fewest solutions first, until a win is found. Moves are in the frame
+/// of `solutions`; `symmetry` maps the board to it.
+fn analyze_endgame(
+    context: &mut SearchContext,
+    solutions: &SolutionTable,
+    symmetry: &Symmetry,
+    node_limit: Option<u64>,
+    stop: &Arc<AtomicBool>,
+    output: &Mutex<dyn Write + Send>,
+) -> io::Result<(Verdict, u64, FullMove)> {
+    if solutions.is_empty() {
+        write_line(output, format_args!("info string no solutions"))?;
+        return Ok((Verdict::Unknown, 0, FullMove::ClaimUnique));
+    }
+    if solutions.len() == 1 {
+        return Ok((Verdict::Win, 0, FullMove::ClaimUnique));
+    }
+
+    let (solutions, mut moves) = solutions.compress_and_gen_moves(&solutions.move_tables());
+    moves.sort_by_key(|x| x.num_solutions);
+    let deadline = Instant::now() + Duration::from_secs(24 * 3600);
+    let endgame_solver = &mut context.endgame_solver;
+    endgame_solver.set_stop_flag(stop.clone());
+    endgame_solver.set_node_limit(node_limit);
+
+    let start_nodes = endgame_solver.total_nodes();
+    let mut nodes: u64 = 0;
+    let mut verdict = Verdict::Loss;
+    // Without a win, the move with the hardest winning reply, or the last unsolved move.
+    let mut best_index = moves.len() - 1;
+    let mut best_difficulty = 0;
+    for (index, mov) in moves.iter().enumerate() {
+        let limit_reached = match node_limit {
+            Some(node_limit) => nodes >= node_limit,
+            None => false,
+        };
+        if limit_reached || stop.load(Ordering::Relaxed) {
+            verdict = Verdict::Unknown;
+            best_index = moves.len() - 1;
+            break;
+        }
+        let new_solutions = solutions.filter(mov.num_solutions, mov.mov);
+        let result = endgame_solver.solve(&new_solutions, None, deadline, None);
+        let move_nodes = endgame_solver.total_nodes() - start_nodes - nodes;
+        nodes += move_nodes;
+        let value = match result {
+            Ok(EndgameResult::Loss) => Verdict::Win,
+            Ok(EndgameResult::Win(reply)) => {
+                if let Some(reply) = reply {
+                    if reply.num_solutions > best_difficulty {
+                        best_index = index;
+                        best_difficulty = reply.num_solutions;
+                    }
+                }
+                Verdict::Loss
+            }
+            Err(_) => {
+                verdict = Verdict::Unknown;
+                best_index = moves.len() - 1;
+                break;
+            }
+        };
+        write_line(
+            output,
+            format_args!(
+                "info move {} solutions {} value {value} nodes {}",
+                symmetry.backward_move(solutions.original_move(mov.mov)),
+                mov.num_solutions,
+                move_nodes
+            ),
+        )?;
+        if value == Verdict::Win {
+            verdict = Verdict::Win;
+            best_index = index;
+            break;
+        }
+    }
+    endgame_solver.clear_stop_flag();
+    endgame_solver.set_node_limit(None);
+
+    let best = &moves[best_index];
+    let mov = solutions.original_move(best.mov);
+    let mov = if verdict == Verdict::Win && best.num_solutions == 1 {
+        FullMove::MoveClaimUnique(mov)
+    } else {
+        FullMove::Move(mov)
+    };
+    Ok((verdict, nodes, mov))
+}
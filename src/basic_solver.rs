@@ -82,14 +82,14 @@ impl Solver for BasicSolver {
 }
 
 #[derive(Clone, Copy, Debug)]
-struct SearchState {
-    board: Board,
+pub(crate) struct SearchState {
+    pub(crate) board: Board,
     line_possibilities: [[[DigitSet; 3]; 3]; 2],
     box_possibilities: [[DigitSet; 3]; 3],
 }
 
 impl SearchState {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         SearchState {
             board: Board::new(),
             line_possibilities: [[[DigitSet::all(); 3]; 3]; 2],
@@ -97,7 +97,7 @@ impl SearchState {
         }
     }
 
-    fn make_move(&mut self, mov: Move) {
+    pub(crate) fn make_move(&mut self, mov: Move) {
         self.board.make_move(mov).unwrap();
         let coord = Coordinates::from(mov.square);
         self.line_possibilities[0][coord.big[0]][coord.small[0]].remove(mov.digit);
@@ -105,7 +105,7 @@ impl SearchState {
         self.box_possibilities[coord.big[0]][coord.big[1]].remove(mov.digit);
     }
 
-    fn possibilities(&self, sq_idx: Small<81>) -> DigitSet {
+    pub(crate) fn possibilities(&self, sq_idx: Small<81>) -> DigitSet {
         let coord = Coordinates::from(sq_idx);
         self.line_possibilities[0][coord.big[0]][coord.small[0]]
             & self.line_possibilities[1][coord.big[1]][coord.small[1]]
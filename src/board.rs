@@ -104,7 +104,7 @@ impl Board {
         }
         Some(FilledBoard {
             // Safety: None of the squares are `NONE` and the representation are all `u8`.
-            squares: unsafe { mem::transmute(self.squares) },
+            squares: unsafe { mem::transmute::<[OptionalDigit; 81], [Digit; 81]>(self.squares) },
         })
     }
 }
@@ -1,30 +1,58 @@
 use crate::{
-    log, platform::platform_description, player::Player, player_main::PlayerMain, settings,
+    board::Move, log, platform::platform_description, player::Player, player_main::PlayerMain,
+    settings::Settings, strategy::Strategies,
 };
 use std::{
     io::{self, BufRead, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc,
+    },
+    thread,
     time::{Duration, Instant},
 };
 
-pub fn run_codecup_interaction() -> io::Result<()> {
-    let mut input = io::stdin().lock();
+/// `opening` moves are played before the game starts. `Start` then means it's our turn.
+pub fn run_codecup_interaction(
+    settings: Settings,
+    strategies: Strategies,
+    opening: Vec<Move>,
+) -> io::Result<()> {
     let mut output = io::stdout().lock();
-    let mut line = String::new();
+    // Set when a line arrives, to stop pondering.
+    let stop = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+    {
+        let stop = stop.clone();
+        thread::spawn(move || read_lines(sender, &stop));
+    }
     let mut time_used = Duration::ZERO;
-    let mut player = None;
-
-    let time_limit = settings::GAME_TIME_LIMIT;
+    let mut player: Option<PlayerMain> = None;
+    let time_limit = settings.game_time_limit;
+    let mut player_args = Some((settings, strategies));
 
     loop {
-        line.clear();
-        if input.read_line(&mut line)? == 0 {
-            return Ok(());
+        if let Some(player) = player.as_mut() {
+            // Time until the opponent's move arrives is not charged to us.
+            player.ponder(&stop);
         }
-        let mut start_time = Instant::now();
+        let Ok(received) = receiver.recv() else {
+            // End of input.
+            return Ok(());
+        };
+        let (line, mut start_time) = received?;
+        // The referee waits for our reply before sending the next line.
+        stop.store(false, Ordering::Relaxed);
 
         if player.is_none() {
             log::write_line!(Info, "platform: {}", platform_description());
-            player = Some(PlayerMain::new());
+            let (settings, strategies) = player_args.take().unwrap();
+            let mut new_player = PlayerMain::with_strategies(settings, strategies);
+            for &mov in &opening {
+                new_player.opponent_move(mov);
+            }
+            player = Some(new_player);
             let t = Instant::now();
             time_used += t.saturating_duration_since(start_time);
             start_time = t;
@@ -63,3 +91,21 @@ pub fn run_codecup_interaction() -> io::Result<()> {
     }
     Ok(())
 }
+
+/// Sends input lines with their arrival times, and sets `stop` for each line.
+fn read_lines(sender: Sender<io::Result<(String, Instant)>>, stop: &AtomicBool) {
+    let mut input = io::stdin().lock();
+    loop {
+        let mut line = String::new();
+        let received = match input.read_line(&mut line) {
+            Ok(0) => return,
+            Ok(_) => Ok((line, Instant::now())),
+            Err(e) => Err(e),
+        };
+        let is_err = received.is_err();
+        stop.store(true, Ordering::Relaxed);
+        if sender.send(received).is_err() || is_err {
+            return;
+        }
+    }
+}
@@ -3,28 +3,81 @@ use crate::{
     digit::Digit,
     digit_set::DigitSet,
     error::ResourcesExceeded,
-    log, settings,
+    log,
+    settings::{self, Settings},
     small::Small,
     solution_table::{EndgameMove, EndgameMoveNoHash, SolutionTable, SquareMoveTable},
-    transposition_table::TranspositionTable,
+    transposition_table::{EndgameTable, TranspositionTable},
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
-use std::time::{Duration, Instant};
 
-pub struct EndgameSolver {
-    transposition_table: TranspositionTable,
+pub struct EndgameSolver<T = TranspositionTable> {
+    transposition_table: T,
     num_nodes: u64,
+    total_nodes: u64,
+    stop: Option<Arc<AtomicBool>>,
+    /// Searches stop when `total_nodes` reaches this.
+    node_limit: Option<u64>,
 }
 
 impl EndgameSolver {
     pub fn new(transposition_table_memory: usize) -> Self {
+        Self::with_table(TranspositionTable::new(transposition_table_memory))
+    }
+}
+
+impl<T: EndgameTable> EndgameSolver<T> {
+    pub fn with_table(transposition_table: T) -> Self {
         Self {
-            transposition_table: TranspositionTable::new(transposition_table_memory),
+            transposition_table,
             num_nodes: 0,
+            total_nodes: 0,
+            stop: None,
+            node_limit: None,
         }
     }
 
+    /// When the flag is set, the search is aborted with `ResourcesExceeded::Time`.
+    pub fn set_stop_flag(&mut self, stop: Arc<AtomicBool>) {
+        self.stop = Some(stop);
+    }
+
+    pub fn clear_stop_flag(&mut self) {
+        self.stop = None;
+    }
+
+    /// Searches are aborted with `ResourcesExceeded::Time` after this many more nodes, in total
+    /// over all following searches.
+    pub fn set_node_limit(&mut self, node_limit: Option<u64>) {
+        self.node_limit = node_limit.map(|limit| self.total_nodes.saturating_add(limit));
+    }
+
+    pub fn num_nodes(&self) -> u64 {
+        self.num_nodes
+    }
+
+    /// Nodes of all searches since the solver was created.
+    pub fn total_nodes(&self) -> u64 {
+        self.total_nodes
+    }
+
+    pub fn transposition_table(&self) -> &T {
+        &self.transposition_table
+    }
+
+    pub fn transposition_table_mut(&mut self) -> &mut T {
+        &mut self.transposition_table
+    }
+
     pub fn choose_move_best_effort(
         &mut self,
+        settings: &Settings,
         solutions: &SolutionTable,
         mut start_time: Instant,
         mut time_left: Duration,
@@ -42,6 +95,34 @@ impl EndgameSolver {
             return FullMove::ClaimUnique;
         }
 
+        if settings.endgame_distance {
+            let deadline = start_time + time_left.mul_f64(settings.endgame_distance_time_fraction);
+            match self.solve_distance_position(solutions, deadline) {
+                Ok(value) => {
+                    log::write_line!(
+                        Info,
+                        "endgame {} in {}",
+                        if value.is_win() { "win" } else { "loss" },
+                        value.distance
+                    );
+                    self.log_stats(start_time, Instant::now());
+                    let mov = value.best_move.unwrap();
+                    return if mov.num_solutions == 1 {
+                        FullMove::MoveClaimUnique(mov.mov)
+                    } else {
+                        FullMove::Move(mov.mov)
+                    };
+                }
+                Err(e) => {
+                    log::write_line!(Info, "endgame distance {e}");
+                    let t = Instant::now();
+                    time_left = time_left.saturating_sub(t.saturating_duration_since(start_time));
+                    start_time = t;
+                    self.num_nodes = 0;
+                }
+            }
+        }
+
         let move_tables = solutions.move_tables();
 
         if let EndgameResult::Win(Some(mov)) = self.check_quick_win(solutions, &move_tables) {
@@ -58,9 +139,9 @@ impl EndgameSolver {
         let num_moves = moves.len();
 
         let offense_deadline =
-            start_time + time_left.mul_f64(settings::ENDGAME_OFFENSE_TIME_FRACTION);
+            start_time + time_left.mul_f64(settings.endgame_offense_time_fraction);
         let offense_deadline_extended =
-            start_time + time_left.mul_f64(settings::ENDGAME_OFFENSE_EXTENDED_TIME_FRACTION);
+            start_time + time_left.mul_f64(settings.endgame_offense_extended_time_fraction);
 
         let mut offense_index = 0;
         let mut best_losing_move_index = num_moves - 1;
@@ -72,7 +153,7 @@ impl EndgameSolver {
                 break;
             }
             let mov = &moves[offense_index];
-            if let Some(difficulty_max) = settings::ENDGAME_OFFENSE_DIFFICULTY_MAX {
+            if let Some(difficulty_max) = settings.endgame_offense_difficulty_max {
                 if mov.num_solutions > difficulty_max {
                     log::write_line!(
                         Info,
@@ -116,20 +197,69 @@ impl EndgameSolver {
             self.num_nodes = 0;
         }
 
+        self.defend(
+            settings,
+            &solutions,
+            &moves[offense_index..],
+            (moves[best_losing_move_index], best_losing_move_difficulty),
+            start_time,
+            time_left,
+        )
+    }
+
+    /// The defense part of `choose_move_best_effort` on all moves, for positions known to be lost.
+    pub fn choose_move_defense(
+        &mut self,
+        settings: &Settings,
+        solutions: &SolutionTable,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> FullMove {
+        self.transposition_table.new_era();
+        self.num_nodes = 0;
+        if solutions.len() <= 1 {
+            return FullMove::ClaimUnique;
+        }
+        let (solutions, mut moves) = solutions.compress_and_gen_moves(&solutions.move_tables());
+        moves.sort_by_key(|x| x.num_solutions);
+        let last_move = *moves.last().unwrap();
+        self.defend(
+            settings,
+            &solutions,
+            &moves,
+            (last_move, 0),
+            start_time,
+            time_left,
+        )
+    }
+
+    /// Tries `moves` from last to first, and plays the first one that can't be refuted.
+    /// Otherwise plays `best_losing_move`, or a move with a harder refutation than its difficulty.
+    fn defend(
+        &mut self,
+        settings: &Settings,
+        solutions: &SolutionTable,
+        moves: &[EndgameMove],
+        best_losing_move: (EndgameMove, u32),
+        mut start_time: Instant,
+        mut time_left: Duration,
+    ) -> FullMove {
+        let num_moves = moves.len();
+        let (mut best_losing_move, mut best_losing_move_difficulty) = best_losing_move;
         let defense_start_time = start_time;
-        for defense_index in (offense_index..num_moves).rev() {
+        for defense_index in (0..num_moves).rev() {
             let mov = &moves[defense_index];
             let defense_deadline =
-                start_time + time_left.mul_f64(settings::ENDGAME_DEFENSE_TIME_FRACTION);
+                start_time + time_left.mul_f64(settings.endgame_defense_time_fraction);
             let defense_deadline_extended =
-                start_time + time_left.mul_f64(settings::ENDGAME_DEFENSE_EXTENDED_TIME_FRACTION);
+                start_time + time_left.mul_f64(settings.endgame_defense_extended_time_fraction);
 
             match self.solve_after_move(
-                &solutions,
+                solutions,
                 mov,
                 Some(defense_deadline),
                 defense_deadline_extended,
-                settings::ENDGAME_DEFENSE_DIFFICULTY_MAX,
+                settings.endgame_defense_difficulty_max,
             ) {
                 Ok(EndgameResult::Loss) => {
                     log::write_line!(
@@ -144,7 +274,7 @@ impl EndgameSolver {
                     // Panic. Reset time for next defensive move.
                     if let Some(opp_mov) = maybe_move {
                         if opp_mov.num_solutions > best_losing_move_difficulty {
-                            best_losing_move_index = defense_index;
+                            best_losing_move = *mov;
                             best_losing_move_difficulty = opp_mov.num_solutions;
                         }
                     }
@@ -169,7 +299,7 @@ impl EndgameSolver {
             "endgame lost difficulty {best_losing_move_difficulty}"
         );
         self.log_stats(defense_start_time, Instant::now());
-        FullMove::Move(solutions.original_move(moves[best_losing_move_index].mov))
+        FullMove::Move(solutions.original_move(best_losing_move.mov))
     }
 
     pub fn solve(
@@ -183,6 +313,165 @@ impl EndgameSolver {
         self.transposition_table.new_era();
         self.num_nodes = 0;
 
+        let result = self.solve_position(solutions, deadline_toplevel, deadline, difficulty_max)?;
+        if self.num_nodes != 0 {
+            self.log_stats(start_time, Instant::now());
+        }
+        Ok(result)
+    }
+
+    /// Exact value: the fastest win, or the longest resistance.
+    pub fn solve_distance(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline: Instant,
+    ) -> Result<EndgameValue, ResourcesExceeded> {
+        let start_time = Instant::now();
+        self.transposition_table.new_era();
+        self.num_nodes = 0;
+
+        let value = self.solve_distance_position(solutions, deadline)?;
+        if self.num_nodes != 0 {
+            self.log_stats(start_time, Instant::now());
+        }
+        Ok(value)
+    }
+
+    /// `solve_distance` without starting a new era.
+    fn solve_distance_position(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline: Instant,
+    ) -> Result<EndgameValue, ResourcesExceeded> {
+        if solutions.is_empty() {
+            log::write_line!(Always, "Error: no solutions!");
+        }
+        if solutions.len() <= 1 {
+            return Ok(EndgameValue {
+                best_move: None,
+                distance: 0,
+            });
+        }
+        // Unlike the transposition table, we also need the losing side's move.
+        let (best_move, distance) = self.solve_distance_moves(solutions, deadline)?;
+        Ok(EndgameValue {
+            best_move: Some(best_move),
+            distance,
+        })
+    }
+
+    fn solve_distance_recursive(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline: Instant,
+    ) -> Result<u8, ResourcesExceeded> {
+        self.num_nodes += 1;
+        self.total_nodes += 1;
+
+        if self.node_limit_reached()
+            || self.num_nodes % settings::ENDGAME_CHECK_TIME_NODES == 0
+                && (Instant::now() >= deadline || self.is_stopped())
+        {
+            return Err(ResourcesExceeded::Time);
+        }
+
+        let (best_move, distance) = self.solve_distance_moves(solutions, deadline)?;
+        let result = if distance % 2 == 1 {
+            EndgameResult::Win(Some(best_move))
+        } else {
+            EndgameResult::Loss
+        };
+        self.transposition_table
+            .insert_distance(solutions.hash(), result, distance);
+        Ok(distance)
+    }
+
+    /// The best move (original) and its distance.
+    ///
+    /// The win/loss search runs first. In a won position only the winning moves are searched, and
+    /// only while they can beat the fastest win so far.
+    fn solve_distance_moves(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline: Instant,
+    ) -> Result<(EndgameMoveNoHash, u8), ResourcesExceeded> {
+        let is_win = matches!(
+            self.solve_position(solutions, None, deadline, None)?,
+            EndgameResult::Win(_)
+        );
+        let (solutions, mut moves) = solutions.compress_and_gen_moves(&solutions.move_tables());
+        moves.sort_by_key(|x| x.num_solutions);
+
+        let mut best: Option<(&EndgameMove, u8)> = None;
+        for mov in moves.iter() {
+            if is_win {
+                let lower_bound = self.winning_distance_lower_bound(mov);
+                if best.map_or(false, |(_, best_distance)| lower_bound >= best_distance) {
+                    continue;
+                }
+                if self.solve_after_move(&solutions, mov, None, deadline, None)?
+                    != EndgameResult::Loss
+                {
+                    continue;
+                }
+            }
+            let distance = self.solve_distance_after_move(&solutions, mov, deadline)? + 1;
+            if best.map_or(true, |(_, best_distance)| {
+                distance_is_better(distance, best_distance)
+            }) {
+                best = Some((mov, distance));
+            }
+        }
+        let (mov, distance) = best.unwrap();
+        Ok((
+            EndgameMoveNoHash {
+                mov: solutions.original_move(mov.mov),
+                num_solutions: mov.num_solutions,
+            },
+            distance,
+        ))
+    }
+
+    /// Lower bound on the distance of `mov`, if it wins.
+    fn winning_distance_lower_bound(&self, mov: &EndgameMove) -> u8 {
+        if mov.num_solutions == 1 {
+            return 1;
+        }
+        match self.transposition_table.find_distance(mov.hash) {
+            Some((_, distance)) => distance + 1,
+            // The opponent can't be left with a unique solution.
+            None => 3,
+        }
+    }
+
+    fn solve_distance_after_move(
+        &mut self,
+        solutions: &SolutionTable,
+        mov: &EndgameMove,
+        deadline: Instant,
+    ) -> Result<u8, ResourcesExceeded> {
+        if mov.num_solutions == 1 {
+            return Ok(0);
+        }
+        if mov.num_solutions < 4 {
+            // There is always a move leaving 1 solution.
+            return Ok(1);
+        }
+        if let Some((_, distance)) = self.transposition_table.find_distance(mov.hash) {
+            return Ok(distance);
+        }
+        let new_solutions = solutions.filter(mov.num_solutions, mov.mov);
+        self.solve_distance_recursive(&new_solutions, deadline)
+    }
+
+    /// `solve` without starting a new era.
+    pub(crate) fn solve_position(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline_toplevel: Option<Instant>,
+        deadline: Instant,
+        difficulty_max: Option<u32>,
+    ) -> Result<EndgameResult, ResourcesExceeded> {
         if solutions.is_empty() {
             log::write_line!(Always, "Error: no solutions!");
             return Ok(EndgameResult::Loss);
@@ -197,10 +486,7 @@ impl EndgameSolver {
             return Ok(result);
         }
 
-        let result =
-            self.solve_recursive(solutions, deadline_toplevel, deadline, difficulty_max)?;
-        self.log_stats(start_time, Instant::now());
-        Ok(result)
+        self.solve_recursive(solutions, deadline_toplevel, deadline, difficulty_max)
     }
 
     fn solve_recursive(
@@ -211,9 +497,11 @@ impl EndgameSolver {
         difficulty_max: Option<u32>,
     ) -> Result<EndgameResult, ResourcesExceeded> {
         self.num_nodes += 1;
+        self.total_nodes += 1;
 
-        if self.num_nodes % settings::ENDGAME_CHECK_TIME_NODES == 0
-            && Instant::now() >= deadline_extended
+        if self.node_limit_reached()
+            || self.num_nodes % settings::ENDGAME_CHECK_TIME_NODES == 0
+                && (Instant::now() >= deadline_extended || self.is_stopped())
         {
             return Err(ResourcesExceeded::Time);
         }
@@ -267,7 +555,7 @@ impl EndgameSolver {
         Ok(result)
     }
 
-    fn solve_after_move(
+    pub(crate) fn solve_after_move(
         &mut self,
         solutions: &SolutionTable,
         mov: &EndgameMove,
@@ -297,7 +585,7 @@ impl EndgameSolver {
         )
     }
 
-    fn check_quick_win(
+    pub(crate) fn check_quick_win(
         &self,
         solutions: &SolutionTable,
         move_tables: &[SquareMoveTable],
@@ -337,6 +625,20 @@ impl EndgameSolver {
         EndgameResult::Loss
     }
 
+    fn node_limit_reached(&self) -> bool {
+        match self.node_limit {
+            Some(node_limit) => self.total_nodes > node_limit,
+            None => false,
+        }
+    }
+
+    fn is_stopped(&self) -> bool {
+        match &self.stop {
+            Some(stop) => stop.load(Ordering::Relaxed),
+            None => false,
+        }
+    }
+
     fn log_stats(&self, start_time: Instant, end_time: Instant) {
         let processing_time = end_time.saturating_duration_since(start_time);
         log::write_line!(
@@ -349,6 +651,30 @@ impl EndgameSolver {
     }
 }
 
+/// Whether distance `a` is better than `b` for the side to move: a faster win, or a slower loss.
+fn distance_is_better(a: u8, b: u8) -> bool {
+    match (a % 2 == 1, b % 2 == 1) {
+        (true, true) => a < b,
+        (false, false) => a > b,
+        (a_wins, _) => a_wins,
+    }
+}
+
+/// Exact value of a position.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EndgameValue {
+    /// The fastest win or the longest resistance (original move). None if the solution is unique.
+    pub best_move: Option<EndgameMoveNoHash>,
+    /// Number of plies until the solution is unique. Odd if the side to move wins.
+    pub distance: u8,
+}
+
+impl EndgameValue {
+    pub fn is_win(&self) -> bool {
+        self.distance % 2 == 1
+    }
+}
+
 /// Win stores the *original* move.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum EndgameResult {
@@ -0,0 +1,177 @@
+use crate::{
+    digit::Digit,
+    endgame::EndgameResult,
+    small::Small,
+    solution_table::{content_id, SolutionTable, DEFAULT_CONTENT_ID_KEY},
+    transposition_table::{pack_result, unpack_result, EndgameTable},
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+};
+
+/// Solved endgame positions that can be saved to disk.
+///
+/// Positions are keyed by `SolutionTable::hash`, so the solution tables must be generated with
+/// `SolutionIds::Content(DEFAULT_CONTENT_ID_KEY)`. Each record also stores the number of solutions
+/// and `SolutionTable::keyed_hash(CHECK_KEY)`, so that a hash collision can't return the result
+/// of another position. Symmetric positions share results if the tables are generated for boards
+/// normalized by `symmetry::normalize_board`.
+///
+/// File format: `MAGIC`, the content ID of a fixed solution (to detect a changed ID scheme),
+/// followed by records of (hash, number of solutions, check hash, packed result). All numbers are
+/// little-endian `u64`.
+pub struct EndgameDatabase {
+    records: HashMap<u64, Record>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Record {
+    num_solutions: u32,
+    check_hash: u64,
+    result: EndgameResult,
+}
+
+impl EndgameDatabase {
+    const MAGIC: [u8; 8] = *b"NDEGDB02";
+    const CHECK_KEY: u64 = 0x436f_6e66_6972_6d73;
+
+    pub fn new() -> Self {
+        Self {
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != Self::MAGIC {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "not an endgame database",
+            ));
+        }
+        if read_u64(&mut reader)? != Some(id_scheme_check()) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "endgame database uses different solution IDs",
+            ));
+        }
+        let mut database = Self::new();
+        while let Some(hash) = read_u64(&mut reader)? {
+            let mut read_field = || {
+                read_u64(&mut reader)?
+                    .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "truncated record"))
+            };
+            let num_solutions = u32::try_from(read_field()?)
+                .map_err(|_| io::Error::new(ErrorKind::InvalidData, "invalid record"))?;
+            let check_hash = read_field()?;
+            let result = unpack_result(read_field()?)
+                .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "invalid result"))?;
+            database.records.insert(
+                hash,
+                Record {
+                    num_solutions,
+                    check_hash,
+                    result,
+                },
+            );
+        }
+        Ok(database)
+    }
+
+    /// Loads the database, or creates an empty one if the file doesn't exist.
+    pub fn load_or_new(path: &Path) -> io::Result<Self> {
+        match Self::load(path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::new()),
+            res => res,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&Self::MAGIC)?;
+        writer.write_all(&id_scheme_check().to_le_bytes())?;
+        let mut records: Vec<(u64, Record)> = self
+            .records
+            .iter()
+            .map(|(&hash, &record)| (hash, record))
+            .collect();
+        records.sort_by_key(|&(hash, _)| hash);
+        for (hash, record) in records {
+            for field in [
+                hash,
+                u64::from(record.num_solutions),
+                record.check_hash,
+                pack_result(record.result),
+            ] {
+                writer.write_all(&field.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn find(&self, solutions: &SolutionTable) -> Option<EndgameResult> {
+        let record = self.records.get(&solutions.hash())?;
+        if record.num_solutions != solutions.len()
+            || record.check_hash != solutions.keyed_hash(Self::CHECK_KEY)
+        {
+            return None;
+        }
+        Some(record.result)
+    }
+
+    pub fn insert(&mut self, solutions: &SolutionTable, result: EndgameResult) {
+        let record = Record {
+            num_solutions: solutions.len(),
+            check_hash: solutions.keyed_hash(Self::CHECK_KEY),
+            result,
+        };
+        let entry = self.records.entry(solutions.hash()).or_insert(record);
+        // Don't overwrite with less complete result.
+        if !matches!(result, EndgameResult::Win(None)) {
+            *entry = record;
+        }
+    }
+
+    /// Inserts the result of `solutions` into a transposition table, if found. Returns whether it
+    /// was found.
+    pub fn preload<T: EndgameTable>(&self, solutions: &SolutionTable, table: &mut T) -> bool {
+        match self.find(solutions) {
+            Some(result) => {
+                table.insert(solutions.hash(), result);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn id_scheme_check() -> u64 {
+    content_id(DEFAULT_CONTENT_ID_KEY, &[Digit::from(Small::new(0)); 81])
+}
+
+/// Returns `None` at end of file.
+fn read_u64(reader: &mut impl Read) -> io::Result<Option<u64>> {
+    let mut bytes = [0u8; 8];
+    let mut len = 0;
+    while len < bytes.len() {
+        match reader.read(&mut bytes[len..])? {
+            0 if len == 0 => return Ok(None),
+            0 => return Err(io::Error::new(ErrorKind::UnexpectedEof, "truncated record")),
+            n => len += n,
+        }
+    }
+    Ok(Some(u64::from_le_bytes(bytes)))
+}
@@ -0,0 +1,251 @@
+use crate::{
+    board::Move, digit::Digit, endgame::EndgameResult, proof_tree::ProofTree, small::Small,
+    solution_table::SolutionTable,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// Slow, simple checker for endgame results.
+///
+/// Plain minimax with memoization on the exact set of solutions. Doesn't use solution IDs,
+/// hashes, or any of the pruning in `EndgameSolver`.
+pub struct EndgameVerifier<'a> {
+    solutions: Vec<&'a [Digit]>,
+    /// Local square for each original square.
+    local_squares: [Option<Small<81>>; 81],
+    /// Whether the side to move wins, by sorted solution indices.
+    memo: HashMap<Vec<u32>, bool>,
+    /// Plies until the solution is unique with best play, by sorted solution indices.
+    distance_memo: HashMap<Vec<u32>, u8>,
+}
+
+impl<'a> EndgameVerifier<'a> {
+    pub fn new(solutions: &'a SolutionTable) -> Self {
+        let mut local_squares = [None; 81];
+        for square in Small::all().take(solutions.num_squares().into()) {
+            let mov = solutions.original_move(Move {
+                square,
+                digit: Digit::from(Small::new(0)),
+            });
+            local_squares[mov.square] = Some(square);
+        }
+        Self {
+            solutions: solutions.iter().map(|solution| solution.digits()).collect(),
+            local_squares,
+            memo: HashMap::new(),
+            distance_memo: HashMap::new(),
+        }
+    }
+
+    /// Whether the side to move wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no solutions.
+    pub fn is_win(&mut self) -> bool {
+        assert!(!self.solutions.is_empty());
+        let position: Vec<u32> = (0..self.solutions.len() as u32).collect();
+        self.is_win_position(&position)
+    }
+
+    /// Number of plies until the solution is unique: the fastest win or the longest resistance.
+    pub fn distance(&mut self) -> u8 {
+        let position: Vec<u32> = (0..self.solutions.len() as u32).collect();
+        self.distance_position(&position)
+    }
+
+    pub fn verify_result(&mut self, result: EndgameResult) -> Result<(), VerificationError> {
+        let root: Vec<u32> = (0..self.solutions.len() as u32).collect();
+        match result {
+            EndgameResult::Loss => {
+                if self.is_win_position(&root) {
+                    return Err(VerificationError::WrongResult);
+                }
+            }
+            EndgameResult::Win(None) => {
+                if !self.is_win_position(&root) {
+                    return Err(VerificationError::WrongResult);
+                }
+            }
+            EndgameResult::Win(Some(mov)) => {
+                let position = self.play(&root, mov.mov)?;
+                if position.len() != mov.num_solutions as usize {
+                    return Err(VerificationError::WrongNumSolutions(mov.mov));
+                }
+                if self.is_win_position(&position) {
+                    return Err(VerificationError::NotWinning(mov.mov));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the tree is a complete proof. Doesn't need a search.
+    pub fn verify_proof_tree(&self, tree: &ProofTree) -> Result<(), VerificationError> {
+        let root: Vec<u32> = (0..self.solutions.len() as u32).collect();
+        self.verify_proof_tree_position(&root, tree)
+    }
+
+    fn verify_proof_tree_position(
+        &self,
+        position: &[u32],
+        tree: &ProofTree,
+    ) -> Result<(), VerificationError> {
+        match tree {
+            ProofTree::Win(proof_move) => {
+                let new_position = self.play(position, proof_move.mov)?;
+                if new_position.len() != proof_move.num_solutions as usize {
+                    return Err(VerificationError::WrongNumSolutions(proof_move.mov));
+                }
+                if proof_move.tree.is_win() {
+                    return Err(VerificationError::NotWinning(proof_move.mov));
+                }
+                self.verify_proof_tree_position(&new_position, &proof_move.tree)
+            }
+            ProofTree::Loss(refutations) => {
+                if position.len() == 1 {
+                    return if refutations.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(VerificationError::IllegalMove(refutations[0].mov))
+                    };
+                }
+                let mut moves = self.legal_moves(position);
+                for proof_move in refutations {
+                    let Some(index) = moves.iter().position(|&(mov, _)| mov == proof_move.mov)
+                    else {
+                        return Err(VerificationError::IllegalMove(proof_move.mov));
+                    };
+                    let (_, num_solutions) = moves.swap_remove(index);
+                    if num_solutions != proof_move.num_solutions {
+                        return Err(VerificationError::WrongNumSolutions(proof_move.mov));
+                    }
+                    if !proof_move.tree.is_win() {
+                        return Err(VerificationError::NotWinning(proof_move.mov));
+                    }
+                    let new_position = self.play(position, proof_move.mov)?;
+                    self.verify_proof_tree_position(&new_position, &proof_move.tree)?;
+                }
+                match moves.first() {
+                    Some(&(mov, _)) => Err(VerificationError::MissingRefutation(mov)),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    fn is_win_position(&mut self, position: &[u32]) -> bool {
+        if position.len() <= 1 {
+            return false;
+        }
+        if let Some(&is_win) = self.memo.get(position) {
+            return is_win;
+        }
+        let mut is_win = false;
+        for (mov, _) in self.legal_moves(position) {
+            let new_position = self.play(position, mov).unwrap();
+            if new_position.len() == 1 || !self.is_win_position(&new_position) {
+                is_win = true;
+                break;
+            }
+        }
+        self.memo.insert(position.to_vec(), is_win);
+        is_win
+    }
+
+    fn distance_position(&mut self, position: &[u32]) -> u8 {
+        if position.len() <= 1 {
+            return 0;
+        }
+        if let Some(&distance) = self.distance_memo.get(position) {
+            return distance;
+        }
+        let mut fastest_win = None;
+        let mut slowest_loss = 0;
+        for (mov, _) in self.legal_moves(position) {
+            let new_position = self.play(position, mov).unwrap();
+            let distance = self.distance_position(&new_position) + 1;
+            if distance == 1 {
+                fastest_win = Some(1);
+                break;
+            }
+            if distance % 2 == 1 {
+                fastest_win = Some(fastest_win.map_or(distance, |d: u8| d.min(distance)));
+            } else {
+                slowest_loss = slowest_loss.max(distance);
+            }
+        }
+        let distance = fastest_win.unwrap_or(slowest_loss);
+        self.distance_memo.insert(position.to_vec(), distance);
+        distance
+    }
+
+    /// Moves (in original coordinates) that keep the position solvable and aren't implied, with
+    /// the number of solutions after each.
+    fn legal_moves(&self, position: &[u32]) -> Vec<(Move, u32)> {
+        let mut moves = Vec::new();
+        for original_square in Small::<81>::all() {
+            let Some(square) = self.local_squares[original_square] else {
+                continue;
+            };
+            let mut counts = [0u32; 9];
+            for &index in position {
+                counts[self.solutions[index as usize][usize::from(square)]] += 1;
+            }
+            for (digit, &count) in Digit::all().zip(counts.iter()) {
+                if count != 0 && count as usize != position.len() {
+                    let mov = Move {
+                        square: original_square,
+                        digit,
+                    };
+                    moves.push((mov, count));
+                }
+            }
+        }
+        moves
+    }
+
+    fn play(&self, position: &[u32], mov: Move) -> Result<Vec<u32>, VerificationError> {
+        let square = self.local_squares[mov.square].ok_or(VerificationError::IllegalMove(mov))?;
+        let new_position: Vec<u32> = position
+            .iter()
+            .copied()
+            .filter(|&index| self.solutions[index as usize][usize::from(square)] == mov.digit)
+            .collect();
+        if new_position.is_empty() || new_position.len() == position.len() {
+            return Err(VerificationError::IllegalMove(mov));
+        }
+        Ok(new_position)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VerificationError {
+    /// The position is won but claimed lost, or vice versa.
+    WrongResult,
+    /// The move makes the sudoku unsolvable or is implied.
+    IllegalMove(Move),
+    /// The number of solutions after the move is wrong.
+    WrongNumSolutions(Move),
+    /// The claimed winning move doesn't win.
+    NotWinning(Move),
+    /// A proof tree doesn't refute this move.
+    MissingRefutation(Move),
+}
+
+impl Display for VerificationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongResult => write!(f, "wrong result"),
+            Self::IllegalMove(mov) => write!(f, "illegal move {mov}"),
+            Self::WrongNumSolutions(mov) => write!(f, "wrong number of solutions after {mov}"),
+            Self::NotWinning(mov) => write!(f, "{mov} doesn't win"),
+            Self::MissingRefutation(mov) => write!(f, "no refutation of {mov}"),
+        }
+    }
+}
+
+impl Error for VerificationError {}
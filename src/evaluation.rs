@@ -0,0 +1,198 @@
+//! Evaluation of positions before the endgame is solved.
+//!
+//! Logistic regression on simple features of the position: the probability that the player to
+//! move wins is `1 / (1 + exp(-weights · features))`. Weights are fitted offline from self-play
+//! games by the `train_evaluation` tool.
+
+use crate::{
+    board::{Board, Coordinates},
+    digit_set::DigitSet,
+    error::InvalidInput,
+    log,
+    midgame::MidgameMove,
+    small::Small,
+    solution_estimator::estimate_solutions,
+    solution_table::SolutionTable,
+    strategy::{self, MidgameDecision, MidgameStrategy, SearchContext},
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+pub const NUM_FEATURES: usize = 7;
+
+pub type Features = [f64; NUM_FEATURES];
+
+/// Natural logarithm of the number of sudoku solutions, for scaling.
+const LN_ALL_SOLUTIONS: f64 = 49.55;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvaluationModel {
+    pub weights: Features,
+}
+
+impl EvaluationModel {
+    /// Weights fitted to 8135 positions from 300 self-play games with 1 second per player, using
+    /// the midgame lower bounds and 100-probe estimates of the number of solutions. The fit is
+    /// weak: log loss 0.6915, against 0.6931 for a coin flip.
+    pub fn new() -> Self {
+        Self {
+            weights: [0.7283, -0.5142, 0.2240, -1.0284, -1.1405, -0.3532, 0.7184],
+        }
+    }
+
+    /// Probability that the player to move wins.
+    pub fn win_probability(&self, features: &Features) -> f64 {
+        let x: f64 = self
+            .weights
+            .iter()
+            .zip(features.iter())
+            .map(|(w, f)| w * f)
+            .sum();
+        1.0 / (1.0 + (-x).exp())
+    }
+
+    /// `num_solutions` is a lower bound on the number of solutions, as from
+    /// `midgame::generate_moves`, and `estimated_solutions` an estimate, as from
+    /// `solution_estimator::estimate_solutions`.
+    pub fn evaluate(&self, board: &Board, num_solutions: f64, estimated_solutions: f64) -> f64 {
+        self.win_probability(&features(board, num_solutions, estimated_solutions))
+    }
+}
+
+/// Features of a position:
+/// * 1
+/// * fraction of squares empty
+/// * parity of the number of empty squares
+/// * average number of candidate digits in empty squares, divided by 9
+/// * fraction of empty squares with 2 candidates
+/// * log of `num_solutions`, scaled to [0, 1]
+/// * log of `estimated_solutions`, scaled to [0, 1]
+///
+/// Candidates are digits not already in the row, column or box.
+pub fn features(board: &Board, num_solutions: f64, estimated_solutions: f64) -> Features {
+    let mut rows = [DigitSet::all(); 9];
+    let mut columns = [DigitSet::all(); 9];
+    let mut boxes = [DigitSet::all(); 9];
+    let index = |coord: Coordinates| {
+        let row = 3 * usize::from(coord.big[0]) + usize::from(coord.small[0]);
+        let column = 3 * usize::from(coord.big[1]) + usize::from(coord.small[1]);
+        let box_ = 3 * usize::from(coord.big[0]) + usize::from(coord.big[1]);
+        (row, column, box_)
+    };
+    for square in Small::<81>::all() {
+        if let Some(digit) = board.square(square).to_digit() {
+            let (row, column, box_) = index(square.into());
+            rows[row].remove(digit);
+            columns[column].remove(digit);
+            boxes[box_].remove(digit);
+        }
+    }
+    let empty = board.empty_squares();
+    let num_empty = empty.size();
+    let mut total_candidates = 0;
+    let mut pairs = 0;
+    for square in empty {
+        let (row, column, box_) = index(square.into());
+        let candidates = (rows[row] & columns[column] & boxes[box_]).size();
+        total_candidates += u32::from(candidates);
+        if candidates == 2 {
+            pairs += 1;
+        }
+    }
+    let per_empty = |x: u32| {
+        if num_empty == 0 {
+            0.0
+        } else {
+            f64::from(x) / f64::from(num_empty)
+        }
+    };
+    [
+        1.0,
+        f64::from(num_empty) / 81.0,
+        f64::from(num_empty % 2),
+        per_empty(total_candidates) / 9.0,
+        per_empty(pairs),
+        (1.0 + num_solutions).ln() / LN_ALL_SOLUTIONS,
+        (1.0 + estimated_solutions).ln() / LN_ALL_SOLUTIONS,
+    ]
+}
+
+impl FromStr for EvaluationModel {
+    type Err = InvalidInput;
+
+    /// Weights separated by `:`, so that they fit in a comma-separated list of settings.
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let mut weights = [0.0; NUM_FEATURES];
+        let mut tokens = s.split(':');
+        for weight in &mut weights {
+            *weight = tokens
+                .next()
+                .ok_or(InvalidInput)?
+                .trim()
+                .parse()
+                .map_err(|_| InvalidInput)?;
+        }
+        if tokens.next().is_some() {
+            return Err(InvalidInput);
+        }
+        Ok(Self { weights })
+    }
+}
+
+impl Display for EvaluationModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, weight) in self.weights.iter().enumerate() {
+            if i != 0 {
+                write!(f, ":")?;
+            }
+            write!(f, "{weight}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Like `DefensiveMidgame`, but tries the moves with the lowest evaluation for the opponent first.
+///
+/// Experimental: the default model barely beats a coin flip, so the move order is close to random.
+pub struct EvaluationMidgame;
+
+impl MidgameStrategy for EvaluationMidgame {
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        board: &mut Board,
+        solutions: &SolutionTable,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> MidgameDecision {
+        let (moves, start_time, time_left) =
+            strategy::generate_midgame_moves(context, board, solutions, start_time, time_left);
+        let model = &context.settings.evaluation_model;
+        let probes = context.settings.evaluation_estimate_probes;
+        let mut evaluated_moves: Vec<(f64, MidgameMove)> = moves
+            .into_iter()
+            .map(|mov| {
+                let mut new_board = *board;
+                new_board.make_move(mov.mov).unwrap();
+                let num_solutions = f64::from(mov.num_solutions_lower_bound);
+                let estimate = estimate_solutions(&new_board, probes, &mut context.rng);
+                (
+                    model.evaluate(&new_board, num_solutions, estimate.estimate),
+                    mov,
+                )
+            })
+            .collect();
+        // Best move last.
+        evaluated_moves.sort_by(|a, b| b.0.total_cmp(&a.0));
+        log::write_line!(
+            Info,
+            "midgame evaluation {:.3}",
+            1.0 - evaluated_moves.last().unwrap().0
+        );
+        let moves: Vec<MidgameMove> = evaluated_moves.into_iter().map(|(_, mov)| mov).collect();
+        strategy::defend(context, board, &moves, start_time, time_left)
+    }
+}
@@ -0,0 +1,284 @@
+//! Game records.
+//!
+//! A record is a list of header lines followed by one line per move:
+//!
+//! ```text
+//! event: CodeCup 2023 round 5
+//! player0: sudoku-game
+//! player1: sudoku-game strategies=endgame=proof-number
+//! time_control: 10000
+//! result: 1
+//! Aa1 time=120
+//! Bd2 time=95 # opening
+//! Ie7! time=2100 verdict=win
+//! ```
+//!
+//! Headers: `player0` moves first. `event` is optional. `time_control` is the time limit per game
+//! in milliseconds. `result` is the index of the winner, followed by `failure` if the loser made
+//! an illegal move or claim; missing if the game didn't finish. `termination` is the reason for a
+//! failure, e.g. `timeout` or `false claim`.
+//!
+//! Moves: `time` is the time spent on the move in milliseconds, `verdict` is the value of the
+//! position before the move for the player to move, `#` starts a comment. All are optional.
+//!
+//! In a file, records are separated by blank lines. Lines starting with `#` are ignored.
+
+use crate::{board::FullMove, error::InvalidInput};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    time::Duration,
+};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GameRecord {
+    pub event: Option<String>,
+    pub players: [String; 2],
+    pub time_control: Option<Duration>,
+    pub result: Option<GameResult>,
+    pub termination: Option<String>,
+    pub moves: Vec<RecordedMove>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GameResult {
+    pub winner: usize,
+    /// The loser made an illegal move or claim.
+    pub failure: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordedMove {
+    pub mov: FullMove,
+    pub time: Option<Duration>,
+    pub verdict: Option<Verdict>,
+    pub comment: Option<String>,
+}
+
+/// Value of a position for the player to move.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    Win,
+    Loss,
+}
+
+impl GameRecord {
+    pub fn new(players: [String; 2]) -> Self {
+        Self {
+            event: None,
+            players,
+            time_control: None,
+            result: None,
+            termination: None,
+            moves: Vec::new(),
+        }
+    }
+
+    /// Parses all records in a file.
+    pub fn parse_all(s: &str) -> Result<Vec<Self>, InvalidInput> {
+        let mut records = Vec::new();
+        let mut lines = Vec::new();
+        for line in s.lines().chain([""]) {
+            if line.trim().is_empty() {
+                if !lines.is_empty() {
+                    records.push(Self::parse_lines(&lines)?);
+                    lines.clear();
+                }
+            } else if !line.starts_with('#') {
+                lines.push(line);
+            }
+        }
+        Ok(records)
+    }
+
+    fn parse_lines(lines: &[&str]) -> Result<Self, InvalidInput> {
+        let mut event = None;
+        let mut players = [None, None];
+        let mut time_control = None;
+        let mut result = None;
+        let mut termination = None;
+        let mut moves = Vec::new();
+        for line in lines {
+            match parse_header(line) {
+                Some((key, value)) => {
+                    if !moves.is_empty() {
+                        return Err(InvalidInput);
+                    }
+                    match key {
+                        "event" => event = Some(value.to_string()),
+                        "player0" => players[0] = Some(value.to_string()),
+                        "player1" => players[1] = Some(value.to_string()),
+                        "time_control" => time_control = Some(parse_millis(value)?),
+                        "result" => result = Some(value.parse()?),
+                        "termination" => termination = Some(value.to_string()),
+                        _ => return Err(InvalidInput),
+                    }
+                }
+                None => moves.push(line.parse()?),
+            }
+        }
+        let [Some(player0), Some(player1)] = players else {
+            return Err(InvalidInput);
+        };
+        Ok(Self {
+            event,
+            players: [player0, player1],
+            time_control,
+            result,
+            termination,
+            moves,
+        })
+    }
+}
+
+/// `key: value` where key is lowercase.
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(':')?;
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return None;
+    }
+    Some((key, value.trim()))
+}
+
+fn parse_millis(s: &str) -> Result<Duration, InvalidInput> {
+    let ms = s.parse().map_err(|_| InvalidInput)?;
+    Ok(Duration::from_millis(ms))
+}
+
+impl FromStr for GameRecord {
+    type Err = InvalidInput;
+
+    /// A single record.
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let mut records = Self::parse_all(s)?;
+        if records.len() != 1 {
+            return Err(InvalidInput);
+        }
+        Ok(records.pop().unwrap())
+    }
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {event}")?;
+        }
+        writeln!(f, "player0: {}", self.players[0])?;
+        writeln!(f, "player1: {}", self.players[1])?;
+        if let Some(time_control) = self.time_control {
+            writeln!(f, "time_control: {}", time_control.as_millis())?;
+        }
+        if let Some(result) = self.result {
+            writeln!(f, "result: {result}")?;
+        }
+        if let Some(termination) = &self.termination {
+            writeln!(f, "termination: {termination}")?;
+        }
+        for mov in &self.moves {
+            writeln!(f, "{mov}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = InvalidInput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let (winner, failure) = match s.split_once(' ') {
+            Some((winner, "failure")) => (winner, true),
+            Some(_) => return Err(InvalidInput),
+            None => (s, false),
+        };
+        let winner = match winner {
+            "0" => 0,
+            "1" => 1,
+            _ => return Err(InvalidInput),
+        };
+        Ok(Self { winner, failure })
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.winner)?;
+        if self.failure {
+            write!(f, " failure")?;
+        }
+        Ok(())
+    }
+}
+
+impl RecordedMove {
+    pub fn new(mov: FullMove) -> Self {
+        Self {
+            mov,
+            time: None,
+            verdict: None,
+            comment: None,
+        }
+    }
+}
+
+impl FromStr for RecordedMove {
+    type Err = InvalidInput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let (s, comment) = match s.split_once('#') {
+            Some((s, comment)) => (s, Some(comment.trim().to_string())),
+            None => (s, None),
+        };
+        let mut tokens = s.split_whitespace();
+        let mut mov = Self::new(tokens.next().ok_or(InvalidInput)?.parse()?);
+        mov.comment = comment;
+        for token in tokens {
+            match token.split_once('=').ok_or(InvalidInput)? {
+                ("time", value) => mov.time = Some(parse_millis(value)?),
+                ("verdict", value) => mov.verdict = Some(value.parse()?),
+                _ => return Err(InvalidInput),
+            }
+        }
+        Ok(mov)
+    }
+}
+
+impl Display for RecordedMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mov)?;
+        if let Some(time) = self.time {
+            write!(f, " time={}", time.as_millis())?;
+        }
+        if let Some(verdict) = self.verdict {
+            write!(f, " verdict={verdict}")?;
+        }
+        if let Some(comment) = &self.comment {
+            write!(f, " # {comment}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Verdict {
+    type Err = InvalidInput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        match s {
+            "win" => Ok(Self::Win),
+            "loss" => Ok(Self::Loss),
+            _ => Err(InvalidInput),
+        }
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Win => write!(f, "win"),
+            Self::Loss => write!(f, "loss"),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Exact canonical form, equivalence and automorphisms of filled grids.
+//!
+//! A symmetry is determined by the transposition, the row that goes to the top and the
+//! permutation of the columns: 2 * 9 * 1296 cases. The top row then fixes the relabeling of the
+//! digits, and the order of the other rows follows from the target grid, or from sorting for the
+//! canonical form, which is the lexicographically smallest image of the grid.
+
+use crate::{
+    board::{row_major_coordinates, FilledBoard},
+    permutation::{Permutation, ALL_PERMUTATIONS_2, ALL_PERMUTATIONS_3},
+    small::Small,
+    symmetry::Symmetry,
+};
+
+/// Digits 0..9, indexed by row and column.
+type Grid = [[u8; 9]; 9];
+
+/// Where each row and column goes, after transposing, and what each digit becomes.
+struct GridMap {
+    transpose: bool,
+    rows: [u8; 9],
+    columns: [u8; 9],
+    digits: [u8; 9],
+}
+
+impl GridMap {
+    fn symmetry(&self) -> Symmetry {
+        let (big0, small0) = axis_permutations(&self.rows);
+        let (big1, small1) = axis_permutations(&self.columns);
+        Symmetry {
+            flip: ALL_PERMUTATIONS_2[usize::from(self.transpose)],
+            big: [big0, big1],
+            small: [small0, small1],
+            digits: Permutation::from_forward(self.digits.map(Small::new)),
+        }
+    }
+}
+
+/// The permutations of the bands (or stacks) and of the lines within each new band.
+fn axis_permutations(map: &[u8; 9]) -> (Permutation<3>, [Permutation<3>; 3]) {
+    let big = Permutation::from_forward([0, 1, 2].map(|b| Small::new(map[3 * b] / 3)));
+    let mut small = [Permutation::identity(); 3];
+    for b in 0..3 {
+        small[usize::from(map[3 * b] / 3)] =
+            Permutation::from_forward([0, 1, 2].map(|i| Small::new(map[3 * b + i] % 3)));
+    }
+    (big, small)
+}
+
+fn grid(board: &FilledBoard, transpose: bool) -> Grid {
+    let mut grid = [[0; 9]; 9];
+    for (i, coord) in row_major_coordinates().enumerate() {
+        let digit = u8::from(Small::<9>::from(board.squares[Small::<81>::from(coord)]));
+        let (row, column) = (i / 9, i % 9);
+        if transpose {
+            grid[column][row] = digit;
+        } else {
+            grid[row][column] = digit;
+        }
+    }
+    grid
+}
+
+/// All 1296 permutations of the columns that keep stacks together.
+fn column_maps() -> Vec<[u8; 9]> {
+    let mut maps = Vec::with_capacity(1296);
+    for big in &ALL_PERMUTATIONS_3 {
+        for small0 in &ALL_PERMUTATIONS_3 {
+            for small1 in &ALL_PERMUTATIONS_3 {
+                for small2 in &ALL_PERMUTATIONS_3 {
+                    let small = [small0, small1, small2];
+                    let mut map = [0; 9];
+                    for (column, x) in map.iter_mut().enumerate() {
+                        let stack = big.forward(Small::new(column as u8 / 3));
+                        let within = small[stack].forward(Small::new(column as u8 % 3));
+                        *x = 3 * u8::from(stack) + u8::from(within);
+                    }
+                    maps.push(map);
+                }
+            }
+        }
+    }
+    maps
+}
+
+/// Permutes the columns and relabels the digits of each row.
+fn map_rows(source: &Grid, columns: &[u8; 9], digits: &[u8; 9]) -> Grid {
+    let mut rows = [[0; 9]; 9];
+    for (row, source_row) in rows.iter_mut().zip(source) {
+        for (column, &digit) in source_row.iter().enumerate() {
+            row[usize::from(columns[column])] = digits[usize::from(digit)];
+        }
+    }
+    rows
+}
+
+/// The lexicographically smallest equivalent grid, and the symmetry mapping `board` to it.
+pub fn canonical_grid(board: &FilledBoard) -> (FilledBoard, Symmetry) {
+    let column_maps = column_maps();
+    let mut best: Option<(Grid, GridMap)> = None;
+    for transpose in [false, true] {
+        let source = grid(board, transpose);
+        for top in 0..9 {
+            let top_band = top / 3;
+            for columns in &column_maps {
+                // The top row becomes 0, 1, ..., 8.
+                let mut digits = [0; 9];
+                for (column, &digit) in source[top].iter().enumerate() {
+                    digits[usize::from(digit)] = columns[column];
+                }
+                let rows = map_rows(&source, columns, &digits);
+
+                // Rows within bands in sorted order, the other bands in sorted order.
+                let sorted_band = |band: usize| {
+                    let mut order = [3 * band, 3 * band + 1, 3 * band + 2];
+                    order.sort_by_key(|&row| rows[row]);
+                    order
+                };
+                let mut top_rows: Vec<usize> = (3 * top_band..3 * top_band + 3)
+                    .filter(|&row| row != top)
+                    .collect();
+                top_rows.sort_by_key(|&row| rows[row]);
+                let mut other_bands: Vec<[usize; 3]> = (0..3)
+                    .filter(|&band| band != top_band)
+                    .map(sorted_band)
+                    .collect();
+                other_bands.sort_by_key(|order| order.map(|row| rows[row]));
+                let order: Vec<usize> = [top]
+                    .into_iter()
+                    .chain(top_rows)
+                    .chain(other_bands.into_iter().flatten())
+                    .collect();
+
+                let mut candidate = [[0; 9]; 9];
+                for (new_row, &row) in candidate.iter_mut().zip(&order) {
+                    *new_row = rows[row];
+                }
+                if best
+                    .as_ref()
+                    .map_or(true, |(best_grid, _)| candidate < *best_grid)
+                {
+                    let mut row_map = [0; 9];
+                    for (new_row, &row) in order.iter().enumerate() {
+                        row_map[row] = new_row as u8;
+                    }
+                    best = Some((
+                        candidate,
+                        GridMap {
+                            transpose,
+                            rows: row_map,
+                            columns: *columns,
+                            digits,
+                        },
+                    ));
+                }
+            }
+        }
+    }
+    let symmetry = best.unwrap().1.symmetry();
+    (symmetry.forward_filled_board(board), symmetry)
+}
+
+/// A symmetry mapping `a` to `b`, if there is one.
+pub fn are_equivalent(a: &FilledBoard, b: &FilledBoard) -> Option<Symmetry> {
+    symmetries_between(a, b, false).pop()
+}
+
+/// All symmetries mapping `board` to itself, including the identity.
+pub fn automorphisms(board: &FilledBoard) -> Vec<Symmetry> {
+    symmetries_between(board, board, true)
+}
+
+/// Symmetries mapping `from` to `to`: all of them, or at most one.
+fn symmetries_between(from: &FilledBoard, to: &FilledBoard, all: bool) -> Vec<Symmetry> {
+    let target = grid(to, false);
+    let column_maps = column_maps();
+    let mut symmetries = Vec::new();
+    for transpose in [false, true] {
+        let source = grid(from, transpose);
+        for top in 0..9 {
+            'columns: for columns in &column_maps {
+                // The top row becomes the target's top row.
+                let mut digits = [0; 9];
+                for (column, &digit) in source[top].iter().enumerate() {
+                    digits[usize::from(digit)] = target[0][usize::from(columns[column])];
+                }
+                let rows = map_rows(&source, columns, &digits);
+                // Rows are distinct, so each can only go to the target row equal to it.
+                let mut row_map = [0; 9];
+                for (row, mapped_row) in rows.iter().enumerate() {
+                    match target
+                        .iter()
+                        .position(|target_row| target_row == mapped_row)
+                    {
+                        Some(new_row) => row_map[row] = new_row as u8,
+                        None => continue 'columns,
+                    }
+                }
+                // Bands must stay together.
+                for band in row_map.chunks(3) {
+                    if band.iter().any(|&row| row / 3 != band[0] / 3) {
+                        continue 'columns;
+                    }
+                }
+                symmetries.push(
+                    GridMap {
+                        transpose,
+                        rows: row_map,
+                        columns: *columns,
+                        digits,
+                    }
+                    .symmetry(),
+                );
+                if !all {
+                    return symmetries;
+                }
+            }
+        }
+    }
+    symmetries
+}
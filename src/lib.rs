@@ -1,6 +1,7 @@
 #![warn(unsafe_op_in_unsafe_fn)]
 #![allow(clippy::new_without_default)]
 
+pub mod analysis; // submission::skip
 pub mod basic_solver; // submission::skip
 pub mod bits;
 pub mod board;
@@ -10,16 +11,28 @@ pub mod digit;
 pub mod digit_box;
 pub mod digit_set;
 pub mod endgame;
+pub mod endgame_database; // submission::skip
+pub mod endgame_verifier; // submission::skip
 pub mod error;
+pub mod evaluation; // submission::skip
 pub mod fast_solver;
+pub mod game_record; // submission::skip
+pub mod grid_symmetry; // submission::skip
 pub mod log;
+pub mod mcts; // submission::skip
 pub mod midgame;
-pub mod permutation; // submission::skip
+pub mod opening_book; // submission::skip
+pub mod opening_suite; // submission::skip
+pub mod parallel_endgame; // submission::skip
+pub mod permutation;
 pub mod platform;
 pub mod player;
 pub mod player_main;
+pub mod proof_number; // submission::skip
+pub mod proof_tree; // submission::skip
 pub mod queue;
 pub mod random;
+pub mod referee; // submission::skip
 pub mod settings;
 pub mod simd128;
 #[cfg(target_feature = "avx2")] // submission::skip
@@ -29,7 +42,9 @@ pub use simd256_emulated as simd256;
 pub mod simd256_emulated;
 pub mod small;
 pub mod small_set;
+pub mod solution_estimator; // submission::skip
 pub mod solution_table;
 pub mod solver;
-pub mod symmetry; // submission::skip
+pub mod strategy;
+pub mod symmetry;
 pub mod transposition_table;
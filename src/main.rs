@@ -1,7 +1,56 @@
-use sudoku_game::{codecup, log}; // submission::skip
+use sudoku_game::{analysis, board, codecup, log, opening_book, settings, strategy}; // submission::skip
 
-fn main() -> std::io::Result<()> {
+use std::{env, io, path::Path};
+
+/// Arguments:
+/// * `--analysis`: analysis protocol instead of CodeCup
+/// * `--book FILE`: opening book, replaces the opening strategy
+/// * `--config FILE`: settings file
+/// * `--opening MOVES`: moves played before the game, e.g. "Aa1,Ed5"
+/// * `NAME=VALUE`: a setting
+///
+/// Settings are applied in order: file, environment, arguments.
+fn main() -> io::Result<()> {
     log::init(log::Level::Info);
-    codecup::run_codecup_interaction()?;
+    let mut settings = settings::Settings::new();
+    let mut config = None;
+    let mut book = None; // submission::skip
+    let mut opening = Vec::new();
+    let mut overrides = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--analysis" => {} // submission::skip
+            "--book" => book = Some(args.next().ok_or_else(invalid_arguments)?), // submission::skip
+            "--config" => config = Some(args.next().ok_or_else(invalid_arguments)?),
+            "--opening" => {
+                opening = args
+                    .next()
+                    .ok_or_else(invalid_arguments)?
+                    .split(',')
+                    .map(str::parse::<board::Move>)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid_arguments())?;
+            }
+            _ => overrides.push(arg),
+        }
+    }
+    if let Some(config) = config {
+        settings.load_file(Path::new(&config))?;
+    }
+    settings.load_env().map_err(|_| invalid_arguments())?;
+    for arg in &overrides {
+        settings.set_all(arg).map_err(|_| invalid_arguments())?;
+    }
+    let strategies = strategy::Strategies::from(settings.strategies);
+    let strategies = opening_book::with_book(strategies, book.as_deref().map(Path::new))?; // submission::skip
+    if env::args().any(|arg| arg == "--analysis") {
+        return analysis::run_analysis_interaction(settings, strategies); // submission::skip
+    }
+    codecup::run_codecup_interaction(settings, strategies, opening)?;
     Ok(())
 }
+
+fn invalid_arguments() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "invalid arguments")
+}
@@ -0,0 +1,373 @@
+//! Monte Carlo tree search for the midgame.
+//!
+//! A node is expanded on its first visit by generating up to `mcts_leaf_solutions` of its
+//! solutions. If that finds all of them, the `EndgameSolver` decides the position exactly.
+//! Otherwise its children are the moves from `midgame::generate_moves` with the most solutions,
+//! and it is valued by a random playout in which the generated solutions stand in for all of them.
+
+use crate::{
+    board::{Board, FullMove, Move},
+    digit::Digit,
+    endgame::EndgameResult,
+    error::ResourcesExceeded,
+    log,
+    midgame::{self, MidgameMove},
+    random::RandomGenerator,
+    settings,
+    small::Small,
+    solution_table::SolutionTable,
+    strategy::{self, MidgameDecision, MidgameStrategy, SearchContext},
+};
+use std::time::{Duration, Instant};
+
+struct Node {
+    /// Filled in with implied squares once expanded.
+    board: Board,
+    /// None at the root.
+    mov: Option<Move>,
+    visits: u32,
+    /// Sum of playout results for the player who made `mov`.
+    score: f64,
+    /// Whether the player to move wins, if known exactly.
+    win: Option<bool>,
+    /// Moves not yet added as children, most solutions last.
+    untried: Vec<MidgameMove>,
+    /// Whether `untried` started out with all legal moves, so that a loss can be proven.
+    all_moves: bool,
+    children: Vec<usize>,
+    /// All solutions, kept for root children that are proven wins for the player who moved.
+    solutions: Option<SolutionTable>,
+}
+
+impl Node {
+    fn new(board: Board, mov: Option<Move>) -> Self {
+        Self {
+            board,
+            mov,
+            visits: 0,
+            score: 0.0,
+            win: None,
+            untried: Vec::new(),
+            all_moves: false,
+            children: Vec::new(),
+            solutions: None,
+        }
+    }
+}
+
+/// Searches until `deadline` or `mcts_iterations_max`, starting from `moves` generated for
+/// `board`.
+///
+/// Plays a proven win if there is one, otherwise the most visited move.
+pub fn search(
+    context: &mut SearchContext,
+    board: &Board,
+    mut moves: Vec<MidgameMove>,
+    deadline: Instant,
+) -> MidgameDecision {
+    assert!(!moves.is_empty());
+    moves.sort_by_key(|x| x.num_solutions_lower_bound);
+    let fallback = moves.last().unwrap().mov;
+    let mut tree = Tree::new(*board, moves, context.settings.mcts_moves_max);
+    let iterations_max = context.settings.mcts_iterations_max.unwrap_or(u64::MAX);
+    let mut iterations: u64 = 0;
+    while tree.nodes[0].win.is_none() && iterations < iterations_max && Instant::now() < deadline {
+        if tree.iterate(context, deadline).is_err() {
+            break;
+        }
+        iterations += 1;
+    }
+    log::write_line!(
+        Info,
+        "mcts iterations={iterations} nodes={nodes}",
+        nodes = tree.nodes.len()
+    );
+
+    let Some(index) = tree.best_child() else {
+        log::write_line!(Info, "mcts no move searched");
+        return MidgameDecision {
+            mov: FullMove::Move(fallback),
+            solutions: None,
+        };
+    };
+    let node = &mut tree.nodes[index];
+    let mov = node.mov.unwrap();
+    log::write_line!(
+        Info,
+        "mcts {mov} visits={visits} score={score:.3} win={win:?}",
+        visits = node.visits,
+        score = node.score / f64::from(node.visits.max(1)),
+        win = node.win.map(|win| !win),
+    );
+    if node.win != Some(false) {
+        return MidgameDecision {
+            mov: FullMove::Move(mov),
+            solutions: None,
+        };
+    }
+    // A proven win: all solutions fit in the leaf table. It is only kept if the win was proven
+    // by the endgame solver, not from the children.
+    let solutions = match node.solutions.take() {
+        Some(solutions) => solutions,
+        None => {
+            let (res, solutions) = SolutionTable::generate_with_ids(
+                &node.board,
+                0,
+                context.settings.mcts_leaf_solutions,
+                deadline,
+                context.solution_ids(),
+            );
+            if res.is_err() {
+                return MidgameDecision {
+                    mov: FullMove::Move(mov),
+                    solutions: None,
+                };
+            }
+            solutions
+        }
+    };
+    let full_move = if solutions.len() == 1 {
+        FullMove::MoveClaimUnique(mov)
+    } else {
+        FullMove::Move(mov)
+    };
+    MidgameDecision {
+        mov: full_move,
+        solutions: Some(solutions),
+    }
+}
+
+struct Tree {
+    nodes: Vec<Node>,
+    moves_max: usize,
+    /// Root moves left out by `moves_max`, most solutions last. Considered when all other root
+    /// moves are proven to lose.
+    root_reserve: Vec<MidgameMove>,
+}
+
+impl Tree {
+    /// `moves` sorted by number of solutions.
+    fn new(board: Board, moves: Vec<MidgameMove>, moves_max: usize) -> Self {
+        let mut tree = Self {
+            nodes: vec![Node::new(board, None)],
+            moves_max,
+            root_reserve: Vec::new(),
+        };
+        tree.root_reserve = tree.set_moves(0, moves);
+        tree
+    }
+
+    /// `moves` sorted by number of solutions. Returns the moves left out by `moves_max`.
+    fn set_moves(&mut self, index: usize, mut moves: Vec<MidgameMove>) -> Vec<MidgameMove> {
+        let node = &mut self.nodes[index];
+        node.untried = moves.split_off(moves.len().saturating_sub(self.moves_max));
+        node.all_moves = moves.is_empty();
+        moves
+    }
+
+    /// One selection, expansion, playout and backpropagation.
+    fn iterate(
+        &mut self,
+        context: &mut SearchContext,
+        deadline: Instant,
+    ) -> Result<(), ResourcesExceeded> {
+        let mut path = vec![0];
+        let mut index = 0;
+        // Result for the player who moved into `index`.
+        let score = loop {
+            let node = &mut self.nodes[index];
+            if let Some(win) = node.win {
+                break if win { 0.0 } else { 1.0 };
+            }
+            if let Some(mov) = node.untried.pop() {
+                let mut board = node.board;
+                board.make_move(mov.mov).unwrap();
+                let child = self.nodes.len();
+                self.nodes.push(Node::new(board, Some(mov.mov)));
+                self.nodes[index].children.push(child);
+                path.push(child);
+                let win = self.expand(context, child, index == 0, deadline)?;
+                break if win { 0.0 } else { 1.0 };
+            }
+            match self.select_child(index, context.settings.mcts_exploration) {
+                Some(child) => {
+                    index = child;
+                    path.push(child);
+                }
+                None if index == 0 && !self.root_reserve.is_empty() => {
+                    let reserve = &mut self.root_reserve;
+                    let moves = reserve.split_off(reserve.len().saturating_sub(self.moves_max));
+                    self.nodes[0].untried = moves;
+                    self.nodes[0].all_moves = self.root_reserve.is_empty();
+                }
+                // All considered moves lose, so the player who moved into `index` wins.
+                None => break 1.0,
+            }
+        };
+
+        let mut score = score;
+        for &index in path.iter().rev() {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.score += score;
+            score = 1.0 - score;
+        }
+        for &index in path.iter().rev().skip(1) {
+            if !self.update_win(index) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates solutions and moves, and returns whether the player to move wins: exactly if
+    /// all solutions were generated, otherwise in a random playout.
+    fn expand(
+        &mut self,
+        context: &mut SearchContext,
+        index: usize,
+        is_root_child: bool,
+        deadline: Instant,
+    ) -> Result<bool, ResourcesExceeded> {
+        let mut board = self.nodes[index].board;
+        let (res, solutions) = SolutionTable::generate_with_ids(
+            &board,
+            0,
+            context.settings.mcts_leaf_solutions,
+            deadline,
+            context.solution_ids(),
+        );
+        match res {
+            Ok(()) => {
+                match context.endgame_solver.solve(
+                    &solutions,
+                    None,
+                    deadline,
+                    context.settings.midgame_defense_difficulty_max,
+                ) {
+                    Ok(result) => {
+                        let win = matches!(result, EndgameResult::Win(_));
+                        self.nodes[index].win = Some(win);
+                        if !win && is_root_child {
+                            self.nodes[index].solutions = Some(solutions);
+                        }
+                        return Ok(win);
+                    }
+                    Err(ResourcesExceeded::Time) if Instant::now() >= deadline => {
+                        return Err(ResourcesExceeded::Time);
+                    }
+                    // Too difficult: use a playout instead.
+                    Err(_) => {}
+                }
+            }
+            Err(ResourcesExceeded::Memory) => {}
+            Err(e) => return Err(e),
+        }
+        if solutions.len() < settings::SOLUTIONS_MIN {
+            return Err(ResourcesExceeded::Time);
+        }
+        let mut moves = midgame::generate_moves(&mut board, &solutions, deadline);
+        if Instant::now() >= deadline {
+            return Err(ResourcesExceeded::Time);
+        }
+        moves.sort_by_key(|x| x.num_solutions_lower_bound);
+        self.nodes[index].board = board;
+        self.set_moves(index, moves);
+        Ok(playout(&solutions, &mut context.rng))
+    }
+
+    /// UCT, skipping moves known to lose.
+    fn select_child(&self, index: usize, exploration: f64) -> Option<usize> {
+        let node = &self.nodes[index];
+        let log_visits = f64::from(node.visits.max(1)).ln();
+        node.children
+            .iter()
+            .copied()
+            .filter(|&child| self.nodes[child].win != Some(true))
+            .max_by(|&a, &b| {
+                let uct = |child: usize| {
+                    let child = &self.nodes[child];
+                    let visits = f64::from(child.visits.max(1));
+                    child.score / visits + exploration * (log_visits / visits).sqrt()
+                };
+                uct(a).total_cmp(&uct(b))
+            })
+    }
+
+    /// Proves a win or a loss from the children. Returns whether it changed.
+    fn update_win(&mut self, index: usize) -> bool {
+        let node = &self.nodes[index];
+        if node.win.is_some() {
+            return false;
+        }
+        let children = node.children.iter().map(|&child| self.nodes[child].win);
+        let win = if children.clone().any(|win| win == Some(false)) {
+            true
+        } else if node.all_moves
+            && node.untried.is_empty()
+            && children.clone().all(|win| win == Some(true))
+        {
+            false
+        } else {
+            return false;
+        };
+        self.nodes[index].win = Some(win);
+        true
+    }
+
+    /// A proven win, otherwise the most visited move not known to lose.
+    fn best_child(&self) -> Option<usize> {
+        let root = &self.nodes[0];
+        let children = || root.children.iter().copied();
+        children()
+            .find(|&child| self.nodes[child].win == Some(false))
+            .or_else(|| {
+                children()
+                    .filter(|&child| {
+                        self.nodes[child].visits != 0 && self.nodes[child].win != Some(true)
+                    })
+                    .max_by_key(|&child| self.nodes[child].visits)
+            })
+            .or_else(|| children().max_by_key(|&child| self.nodes[child].visits))
+    }
+}
+
+/// Random moves until one solution is left, as if `solutions` were all of them. Returns whether
+/// the player to move wins.
+fn playout(solutions: &SolutionTable, rng: &mut RandomGenerator) -> bool {
+    let mut remaining: Vec<&[Digit]> = solutions.iter().map(|s| s.digits()).collect();
+    let mut squares: Vec<Small<81>> = Vec::with_capacity(81);
+    let mut player_to_move_wins = false;
+    while remaining.len() > 1 {
+        squares.clear();
+        squares.extend(Small::<81>::all().filter(|&square| {
+            let square = usize::from(square);
+            let digit = remaining[0][square];
+            remaining.iter().any(|solution| solution[square] != digit)
+        }));
+        let square = usize::from(*rng.choose(&squares));
+        let digit = rng.choose(&remaining)[square];
+        remaining.retain(|solution| solution[square] == digit);
+        player_to_move_wins = !player_to_move_wins;
+    }
+    player_to_move_wins
+}
+
+/// Monte Carlo tree search, see `mcts`.
+pub struct MctsMidgame;
+
+impl MidgameStrategy for MctsMidgame {
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        board: &mut Board,
+        solutions: &SolutionTable,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> MidgameDecision {
+        let (moves, start_time, time_left) =
+            strategy::generate_midgame_moves(context, board, solutions, start_time, time_left);
+        let deadline = start_time + time_left.mul_f64(context.settings.mcts_time_fraction);
+        search(context, board, moves, deadline)
+    }
+}
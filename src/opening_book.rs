@@ -0,0 +1,267 @@
+//! Opening book: statistics of moves from played games, keyed by normalized boards.
+//!
+//! A book file is a list of positions separated by blank lines:
+//!
+//! ```text
+//! position: 1..3.....2.....................1.................................................
+//! verdict: loss
+//! Ab4 wins=3 losses=5
+//! Ee5 wins=7 losses=2
+//! ```
+//!
+//! `position` is normalized by `symmetry::normalize_board` and moves are relative to it.
+//! `verdict` is the known value of the position for the player to move, if any. The
+//! `opening_book` tool doesn't derive verdicts, so they are added by hand. `wins` and `losses` are
+//! game results for the player making the move. Lines starting with `#` are ignored.
+
+use crate::{
+    board::{Board, FullMove, Move},
+    error::InvalidInput,
+    log,
+    referee::Referee,
+    strategy::{OpeningStrategy, RandomOpening, SearchContext, Strategies},
+    symmetry::normalize_board,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpeningBook {
+    positions: BTreeMap<Board, BookPosition>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BookPosition {
+    /// Whether the player to move wins, if known.
+    pub win: Option<bool>,
+    pub moves: Vec<BookMove>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BookMove {
+    pub mov: Move,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+impl BookMove {
+    /// Expected score with one virtual win and one virtual loss.
+    pub fn score(&self) -> f64 {
+        f64::from(self.wins + 1) / f64::from(self.wins + self.losses + 2)
+    }
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of positions.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The entry of a normalized board.
+    pub fn position(&self, normalized_board: &Board) -> Option<&BookPosition> {
+        self.positions.get(normalized_board)
+    }
+
+    /// Records a game in which `mov` was played on `board`.
+    pub fn add_game_move(&mut self, board: &Board, mov: Move, win: bool) {
+        let (normalized_board, symmetry) = normalize_board(board);
+        let mov = symmetry.forward_move(mov);
+        let position = self.positions.entry(normalized_board).or_default();
+        let book_move = match position.moves.iter_mut().find(|m| m.mov == mov) {
+            Some(book_move) => book_move,
+            None => {
+                position.moves.push(BookMove {
+                    mov,
+                    wins: 0,
+                    losses: 0,
+                });
+                position.moves.last_mut().unwrap()
+            }
+        };
+        if win {
+            book_move.wins += 1;
+        } else {
+            book_move.losses += 1;
+        }
+    }
+
+    /// Records whether the player to move on `board` wins.
+    pub fn set_win(&mut self, board: &Board, win: bool) {
+        let (normalized_board, _) = normalize_board(board);
+        self.positions.entry(normalized_board).or_default().win = Some(win);
+    }
+
+    /// A move into a position known to be lost for the opponent, otherwise the move with the best
+    /// score if it is at least 1/2. Moves into positions known to be won for the opponent are
+    /// avoided.
+    pub fn choose_move(&self, board: &Board) -> Option<Move> {
+        let (normalized_board, symmetry) = normalize_board(board);
+        let position = self.positions.get(&normalized_board)?;
+        let mut best: Option<(f64, Move)> = None;
+        for book_move in &position.moves {
+            let mut new_board = normalized_board;
+            if new_board.make_move(book_move.mov).is_err() {
+                continue;
+            }
+            match self.positions.get(&normalize_board(&new_board).0) {
+                Some(BookPosition {
+                    win: Some(false), ..
+                }) => return Some(symmetry.backward_move(book_move.mov)),
+                Some(BookPosition {
+                    win: Some(true), ..
+                }) => continue,
+                _ => {}
+            }
+            let score = book_move.score();
+            if score >= 0.5 && best.map_or(true, |(best_score, _)| score > best_score) {
+                best = Some((score, book_move.mov));
+            }
+        }
+        best.map(|(_, mov)| symmetry.backward_move(mov))
+    }
+}
+
+impl FromStr for OpeningBook {
+    type Err = InvalidInput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let mut book = Self::new();
+        let mut current: Option<Board> = None;
+        for line in s.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            if line.is_empty() {
+                current = None;
+            } else if let Some(board) = line.strip_prefix("position:") {
+                let board: Board = board.trim().parse()?;
+                if book.positions.contains_key(&board) {
+                    return Err(InvalidInput);
+                }
+                book.positions.insert(board, BookPosition::default());
+                current = Some(board);
+            } else {
+                let position = book
+                    .positions
+                    .get_mut(&current.ok_or(InvalidInput)?)
+                    .unwrap();
+                if let Some(verdict) = line.strip_prefix("verdict:") {
+                    position.win = Some(match verdict.trim() {
+                        "win" => true,
+                        "loss" => false,
+                        _ => return Err(InvalidInput),
+                    });
+                } else {
+                    position.moves.push(line.parse()?);
+                }
+            }
+        }
+        Ok(book)
+    }
+}
+
+impl FromStr for BookMove {
+    type Err = InvalidInput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let mut tokens = s.split_whitespace();
+        let mov = tokens.next().ok_or(InvalidInput)?.parse()?;
+        let mut wins = 0;
+        let mut losses = 0;
+        for token in tokens {
+            let (name, value) = token.split_once('=').ok_or(InvalidInput)?;
+            let value = value.parse().map_err(|_| InvalidInput)?;
+            match name {
+                "wins" => wins = value,
+                "losses" => losses = value,
+                _ => return Err(InvalidInput),
+            }
+        }
+        Ok(Self { mov, wins, losses })
+    }
+}
+
+impl Display for OpeningBook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, (board, position)) in self.positions.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "position: {board}")?;
+            if let Some(win) = position.win {
+                writeln!(f, "verdict: {}", if win { "win" } else { "loss" })?;
+            }
+            for book_move in &position.moves {
+                writeln!(
+                    f,
+                    "{} wins={} losses={}",
+                    book_move.mov, book_move.wins, book_move.losses
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Legal book moves while there are any and at most `opening_max_squares` squares are filled,
+/// then `RandomOpening`.
+pub struct BookOpening {
+    book: OpeningBook,
+}
+
+impl BookOpening {
+    pub fn new(book: OpeningBook) -> Self {
+        Self { book }
+    }
+}
+
+/// `strategies` with the opening replaced by a `BookOpening` from the book file at `path`, if
+/// any.
+pub fn with_book(mut strategies: Strategies, path: Option<&Path>) -> io::Result<Strategies> {
+    if let Some(path) = path {
+        let book = fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        strategies.opening = Box::new(BookOpening::new(book));
+    }
+    Ok(strategies)
+}
+
+impl OpeningStrategy for BookOpening {
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        board: &mut Board,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> Option<Move> {
+        if 81 - board.empty_squares().size() <= context.settings.opening_max_squares {
+            if let Some(mov) = self.book.choose_move(board) {
+                match Referee::with_board(*board).make_move(FullMove::Move(mov)) {
+                    Ok(()) => {
+                        log::write_line!(Info, "opening book {mov}");
+                        return Some(mov);
+                    }
+                    Err(e) => log::write_line!(Info, "opening book {mov} {e}"),
+                }
+            }
+        }
+        RandomOpening.choose_move(context, board, start_time, time_left)
+    }
+}
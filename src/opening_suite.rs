@@ -0,0 +1,178 @@
+//! Opening suites: starting positions for engine matches.
+//!
+//! A suite file has one opening per line: moves separated by spaces or commas, e.g.
+//! `Aa1 Ed5 Ii9`, or a board optionally followed by moves, e.g. `509032070003... Ed1 Fe2`. The
+//! filled squares of a board are played first, in an order in which none of them is implied.
+//! Blank lines and lines starting with `#` are ignored.
+
+use crate::{
+    board::{row_major_coordinates, Board, FullMove, Move},
+    error::InvalidInput,
+    referee::{IllegalMove, Referee},
+    small::Small,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Opening {
+    pub moves: Vec<Move>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OpeningError {
+    /// Not a board or moves.
+    InvalidInput,
+    /// The move is illegal after the moves before it.
+    IllegalMove(Move, IllegalMove),
+}
+
+impl From<InvalidInput> for OpeningError {
+    fn from(_: InvalidInput) -> Self {
+        Self::InvalidInput
+    }
+}
+
+impl Display for OpeningError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidInput => write!(f, "invalid input"),
+            Self::IllegalMove(mov, reason) => write!(f, "illegal move {mov}: {reason}"),
+        }
+    }
+}
+
+impl Error for OpeningError {}
+
+/// An invalid opening in a suite file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SuiteError {
+    /// Line number, starting from 1.
+    pub line: usize,
+    pub error: OpeningError,
+}
+
+impl Display for SuiteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "opening suite line {}: {}", self.line, self.error)
+    }
+}
+
+impl Error for SuiteError {}
+
+impl Opening {
+    /// Plays the opening. Fails if a move is illegal.
+    pub fn referee(&self) -> Result<Referee, OpeningError> {
+        let mut referee = Referee::new();
+        for &mov in &self.moves {
+            referee
+                .make_move(FullMove::Move(mov))
+                .map_err(|reason| OpeningError::IllegalMove(mov, reason))?;
+        }
+        Ok(referee)
+    }
+
+    /// Moves separated by commas, for the `--opening` argument of the main binary.
+    pub fn to_argument(&self) -> String {
+        let moves: Vec<String> = self.moves.iter().map(Move::to_string).collect();
+        moves.join(",")
+    }
+}
+
+/// Parses and checks all openings in a suite file.
+pub fn parse_suite(s: &str) -> Result<Vec<Opening>, SuiteError> {
+    let mut openings = Vec::new();
+    for (index, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let opening = line
+            .parse::<Opening>()
+            .and_then(|opening| opening.referee().map(|_| opening))
+            .map_err(|error| SuiteError {
+                line: index + 1,
+                error,
+            })?;
+        openings.push(opening);
+    }
+    Ok(openings)
+}
+
+impl TryFrom<&Board> for Opening {
+    type Error = OpeningError;
+
+    /// Orders the filled squares from the last one: any square that isn't implied by all the
+    /// others can be last, because leaving it out keeps the squares before it legal. Fails with a
+    /// square that is implied by all the others if there is no such order.
+    fn try_from(board: &Board) -> Result<Self, OpeningError> {
+        let mut clues: Vec<Move> = row_major_coordinates()
+            .filter_map(|coord| {
+                let square = Small::<81>::from(coord);
+                let digit = board.square(square).to_digit()?;
+                Some(Move { square, digit })
+            })
+            .collect();
+        let mut moves = Vec::with_capacity(clues.len());
+        while !clues.is_empty() {
+            let mut error = None;
+            // Prefer later clues, to stay close to row-major order.
+            let index = clues.iter().rposition(|&mov| {
+                let mut others = Board::new();
+                for &other in clues.iter().filter(|&&other| other != mov) {
+                    others.make_move(other).unwrap();
+                }
+                match Referee::with_board(others).make_move(FullMove::Move(mov)) {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        error.get_or_insert(OpeningError::IllegalMove(mov, reason));
+                        false
+                    }
+                }
+            });
+            let Some(index) = index else {
+                return Err(error.unwrap());
+            };
+            moves.push(clues.remove(index));
+        }
+        moves.reverse();
+        Ok(Self { moves })
+    }
+}
+
+impl FromStr for Opening {
+    type Err = OpeningError;
+
+    fn from_str(s: &str) -> Result<Self, OpeningError> {
+        let mut tokens = s
+            .split([' ', ','])
+            .filter(|token| !token.is_empty())
+            .peekable();
+        let mut opening = match tokens.peek() {
+            Some(token) if token.len() == 81 => {
+                let board: Board = tokens.next().unwrap().parse()?;
+                Self::try_from(&board)?
+            }
+            _ => Self::default(),
+        };
+        for token in tokens {
+            opening.moves.push(token.parse()?);
+        }
+        Ok(opening)
+    }
+}
+
+impl Display for Opening {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, mov) in self.moves.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{mov}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    endgame::{EndgameResult, EndgameSolver},
+    error::ResourcesExceeded,
+    log,
+    solution_table::{EndgameMove, EndgameMoveNoHash, SolutionTable},
+    transposition_table::SharedTranspositionTable,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+/// Endgame solver that splits the root moves between worker threads.
+///
+/// The workers share a transposition table. The result is the same as from `EndgameSolver::solve`:
+/// if there are several winning moves, the first one in the serial search order is returned.
+pub struct ParallelEndgameSolver {
+    transposition_table: SharedTranspositionTable,
+    num_threads: usize,
+}
+
+impl ParallelEndgameSolver {
+    pub fn new(transposition_table_memory: usize, num_threads: usize) -> Self {
+        assert!(num_threads >= 1);
+        Self {
+            transposition_table: SharedTranspositionTable::new(transposition_table_memory),
+            num_threads,
+        }
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    pub fn transposition_table(&self) -> &SharedTranspositionTable {
+        &self.transposition_table
+    }
+
+    pub fn solve(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline_toplevel: Option<Instant>,
+        deadline: Instant,
+        difficulty_max: Option<u32>,
+    ) -> Result<EndgameResult, ResourcesExceeded> {
+        let start_time = Instant::now();
+        self.transposition_table.new_era();
+
+        if solutions.is_empty() {
+            log::write_line!(Always, "Error: no solutions!");
+            return Ok(EndgameResult::Loss);
+        }
+        if solutions.len() == 1 {
+            return Ok(EndgameResult::Loss);
+        }
+        if solutions.len() < 4 {
+            return Ok(EndgameResult::Win(None));
+        }
+        if let Some(result) = self.transposition_table.find(solutions.hash()) {
+            return Ok(result);
+        }
+
+        let move_tables = solutions.move_tables();
+        let root_solver = EndgameSolver::with_table(&self.transposition_table);
+        let result = root_solver.check_quick_win(solutions, &move_tables);
+        if let EndgameResult::Win(_) = result {
+            self.transposition_table.insert(solutions.hash(), result);
+            return Ok(result);
+        }
+
+        let (compressed, mut moves) = solutions.compress_and_gen_moves(&move_tables);
+        moves.sort_by_key(|x| x.num_solutions);
+
+        // The serial search fails when it reaches a move that is too difficult.
+        let mut num_moves = moves.len();
+        let mut difficulty_error = None;
+        if let Some(difficulty_max) = difficulty_max {
+            if let Some(index) = moves.iter().position(|x| x.num_solutions > difficulty_max) {
+                num_moves = index;
+                difficulty_error = Some(ResourcesExceeded::Difficulty(moves[index].num_solutions));
+            }
+        }
+
+        let search = RootSearch {
+            solutions: &compressed,
+            moves: &moves[..num_moves],
+            deadline_toplevel,
+            deadline,
+            transposition_table: &self.transposition_table,
+            next_index: AtomicUsize::new(0),
+            cutoff: Mutex::new(None),
+            losing_move_source: Mutex::new([[usize::MAX; 9]; 81]),
+            workers: (0..self.num_threads)
+                .map(|_| WorkerState {
+                    index: AtomicUsize::new(0),
+                    stop: Arc::new(AtomicBool::new(false)),
+                })
+                .collect(),
+            num_nodes: AtomicU64::new(0),
+        };
+
+        thread::scope(|scope| {
+            for worker in 0..self.num_threads {
+                let search = &search;
+                scope.spawn(move || search.run_worker(worker));
+            }
+        });
+
+        let num_nodes = search.num_nodes.load(Ordering::Relaxed);
+        let result = match search.cutoff.into_inner().unwrap() {
+            Some(Cutoff {
+                result: Ok(mov), ..
+            }) => EndgameResult::Win(Some(mov)),
+            Some(Cutoff { result: Err(e), .. }) => return Err(e),
+            None => match difficulty_error {
+                Some(e) => return Err(e),
+                None => EndgameResult::Loss,
+            },
+        };
+        self.transposition_table.insert(solutions.hash(), result);
+
+        let processing_time = Instant::now().saturating_duration_since(start_time);
+        log::write_line!(
+            Info,
+            "threads: {} nodes: {} time: {:.3?} knps: {:.1}",
+            self.num_threads,
+            num_nodes,
+            processing_time,
+            num_nodes as f64 / processing_time.as_secs_f64() / 1000.0
+        );
+        Ok(result)
+    }
+}
+
+struct RootSearch<'a> {
+    solutions: &'a SolutionTable,
+    moves: &'a [EndgameMove],
+    deadline_toplevel: Option<Instant>,
+    deadline: Instant,
+    transposition_table: &'a SharedTranspositionTable,
+    next_index: AtomicUsize,
+    /// The first move (in search order) that decides the result.
+    cutoff: Mutex<Option<Cutoff>>,
+    /// For each move known to lose, the index of the move that proved it.
+    losing_move_source: Mutex<[[usize; 9]; 81]>,
+    workers: Vec<WorkerState>,
+    num_nodes: AtomicU64,
+}
+
+struct WorkerState {
+    /// Index of the move being searched.
+    index: AtomicUsize,
+    stop: Arc<AtomicBool>,
+}
+
+struct Cutoff {
+    index: usize,
+    /// A winning move, or the reason the move couldn't be solved.
+    result: Result<EndgameMoveNoHash, ResourcesExceeded>,
+}
+
+impl RootSearch<'_> {
+    fn run_worker(&self, worker: usize) {
+        let state = &self.workers[worker];
+        let mut solver = EndgameSolver::with_table(self.transposition_table);
+        solver.set_stop_flag(state.stop.clone());
+
+        loop {
+            let index = self.next_index.fetch_add(1, Ordering::Relaxed);
+            if index >= self.moves.len() {
+                break;
+            }
+            state.index.store(index, Ordering::Relaxed);
+            if self.is_cut_off(index) {
+                break;
+            }
+            if let Some(deadline_toplevel) = self.deadline_toplevel {
+                if Instant::now() >= deadline_toplevel {
+                    self.set_cutoff(index, Err(ResourcesExceeded::Time));
+                    break;
+                }
+            }
+
+            let mov = &self.moves[index];
+            let orig_mov = self.solutions.original_move(mov.mov);
+            // Only use what the serial search would have known at this point.
+            if self.losing_move_source.lock().unwrap()[orig_mov.square][orig_mov.digit] < index {
+                // Store without move -- we don't know whether mov is the best response.
+                self.transposition_table
+                    .insert(mov.hash, EndgameResult::Win(None));
+                continue;
+            }
+
+            match solver.solve_after_move(self.solutions, mov, None, self.deadline, None) {
+                Ok(EndgameResult::Loss) => {
+                    self.set_cutoff(
+                        index,
+                        Ok(EndgameMoveNoHash {
+                            mov: orig_mov,
+                            num_solutions: mov.num_solutions,
+                        }),
+                    );
+                }
+                Ok(EndgameResult::Win(None)) => {}
+                Ok(EndgameResult::Win(Some(orig_opp_mov))) => {
+                    // Same transposition trick as in `EndgameSolver::solve_recursive`.
+                    let mut losing_move_source = self.losing_move_source.lock().unwrap();
+                    let source =
+                        &mut losing_move_source[orig_opp_mov.mov.square][orig_opp_mov.mov.digit];
+                    *source = (*source).min(index);
+                }
+                Err(e) => {
+                    // If we were stopped, a move earlier in the search order already decided.
+                    if !state.stop.load(Ordering::Relaxed) {
+                        self.set_cutoff(index, Err(e));
+                    }
+                }
+            }
+        }
+        self.num_nodes
+            .fetch_add(solver.num_nodes(), Ordering::Relaxed);
+    }
+
+    fn is_cut_off(&self, index: usize) -> bool {
+        match &*self.cutoff.lock().unwrap() {
+            Some(cutoff) => cutoff.index < index,
+            None => false,
+        }
+    }
+
+    fn set_cutoff(&self, index: usize, result: Result<EndgameMoveNoHash, ResourcesExceeded>) {
+        let mut cutoff = self.cutoff.lock().unwrap();
+        if let Some(c) = &*cutoff {
+            if c.index < index {
+                return;
+            }
+        }
+        *cutoff = Some(Cutoff { index, result });
+        // Moves later in the search order no longer matter.
+        for worker in &self.workers {
+            if worker.index.load(Ordering::Relaxed) > index {
+                worker.stop.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
@@ -17,6 +17,23 @@ impl<const L: usize> Permutation<L> {
         Self { forward, backward }
     }
 
+    /// `forward[i]` is where `i` goes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `forward` is not a permutation.
+    pub fn from_forward(forward: [Small<L>; L]) -> Self {
+        let mut backward = [None; L];
+        for i in Small::all() {
+            assert!(backward[usize::from(forward[i])].is_none());
+            backward[usize::from(forward[i])] = Some(i);
+        }
+        Self {
+            forward,
+            backward: backward.map(Option::unwrap),
+        }
+    }
+
     pub fn swap_forward(&mut self, i: Small<L>, j: Small<L>) {
         self.forward.swap(i.into(), j.into());
         self.backward
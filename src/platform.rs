@@ -22,7 +22,7 @@ pub fn platform_description() -> String {
     check_features!(
         "avx",
         "avx2",
-        "bmi",
+        "bmi1",
         "bmi2",
         "fma",
         "lzcnt",
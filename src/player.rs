@@ -1,7 +1,13 @@
 use crate::board::{FullMove, Move};
-use std::time::{Duration, Instant};
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
 
 pub trait Player {
     fn opponent_move(&mut self, mov: Move);
     fn choose_move(&mut self, start_time: Instant, time_left: Duration) -> FullMove;
+
+    /// Thinks on the opponent's time until `stop` is set or there is nothing more to do.
+    fn ponder(&mut self, _stop: &Arc<AtomicBool>) {}
 }
@@ -1,167 +1,44 @@
-use std::time::{Duration, Instant};
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
 
 use crate::{
     board::{Board, FullMove, Move},
-    endgame::{EndgameResult, EndgameSolver},
-    error::{InvalidInput, ResourcesExceeded},
-    log, midgame,
+    error::InvalidInput,
+    log,
     player::Player,
-    random::RandomGenerator,
-    settings,
+    settings::{self, Settings},
+    small::Small,
     solution_table::SolutionTable,
+    strategy::{MidgameDecision, SearchContext, Strategies},
+    symmetry::Symmetry,
 };
 
 pub struct PlayerMain {
     board: Board,
     all_solutions_generated: bool,
+    /// Solutions of `symmetry.forward_board(&board)`.
     solutions: SolutionTable,
-    endgame_solver: EndgameSolver,
-    rng: RandomGenerator,
+    symmetry: Symmetry,
+    context: SearchContext,
+    strategies: Strategies,
 }
 
 impl PlayerMain {
     pub fn new() -> Self {
+        Self::with_strategies(Settings::new(), Strategies::new())
+    }
+
+    pub fn with_strategies(settings: Settings, strategies: Strategies) -> Self {
         Self {
             board: Board::new(),
             all_solutions_generated: false,
             solutions: SolutionTable::empty(),
-            endgame_solver: EndgameSolver::new(settings::TRANSPOSITION_TABLE_MEMORY),
-            rng: RandomGenerator::with_time_nonce(),
-        }
-    }
-
-    fn choose_opening_move(&mut self, start_time: Instant, time_left: Duration) -> Option<Move> {
-        let movegen_deadline =
-            start_time + time_left.mul_f64(settings::OPENING_MOVEGEN_TIME_FRACTION);
-        let (res, solutions) =
-            SolutionTable::generate(&self.board, 2, 2, movegen_deadline, &mut self.rng);
-        match res {
-            Ok(()) => {
-                log::write_line!(Info, "What? Opening all solutions generated.",);
-                return None;
-            }
-            Err(ResourcesExceeded::Memory) => {}
-            Err(e) => {
-                log::write_line!(Info, "opening sol gen error: {e}",);
-                return None;
-            }
-        }
-        let moves = midgame::generate_moves(&mut self.board, &solutions, movegen_deadline);
-        assert!(!moves.is_empty());
-        log::write_line!(
-            Info,
-            "opening movegen time {:.3?}",
-            Instant::now().saturating_duration_since(start_time)
-        );
-        if 81 - self.board.empty_squares().size() <= settings::OPENING_MAX_SQUARES {
-            Some(self.rng.choose(&moves).mov)
-        } else {
-            None
-        }
-    }
-
-    fn midgame_choose_move_best_effort(
-        &mut self,
-        mut start_time: Instant,
-        mut time_left: Duration,
-    ) -> FullMove {
-        assert!(self.solutions.len() >= settings::SOLUTIONS_MIN);
-        let movegen_deadline =
-            start_time + time_left.mul_f64(settings::MIDGAME_MOVEGEN_TIME_FRACTION);
-        let mut moves = midgame::generate_moves(&mut self.board, &self.solutions, movegen_deadline);
-        let num_moves = moves.len();
-        assert!(!moves.is_empty());
-        moves.sort_by_key(|x| x.num_solutions_lower_bound);
-
-        {
-            let t = Instant::now();
-            let used_time = t.saturating_duration_since(start_time);
-            time_left = time_left.saturating_sub(used_time);
-            start_time = t;
-            log::write_line!(Info, "midgame movegen time {used_time:.3?}",);
-        }
-
-        let mut best_losing_move_index = num_moves - 1;
-        let mut best_losing_move_difficulty = 0;
-
-        for (defense_index, mov) in moves.iter().enumerate().rev() {
-            if mov.num_solutions_lower_bound <= settings::MIDGAME_DEFENSE_SOLUTIONS_MAX {
-                let defense_deadline =
-                    start_time + time_left.mul_f64(settings::MIDGAME_DEFENSE_TIME_FRACTION);
-                let defense_deadline_extended = start_time
-                    + time_left.mul_f64(settings::MIDGAME_DEFENSE_EXTENDED_TIME_FRACTION);
-                let mut new_board = self.board;
-                new_board.make_move(mov.mov).unwrap();
-                let (solgen_result, solutions) = SolutionTable::generate(
-                    &new_board,
-                    0,
-                    settings::MIDGAME_DEFENSE_SOLUTIONS_MAX,
-                    defense_deadline,
-                    &mut self.rng,
-                );
-                if let Err(e) = solgen_result {
-                    log::write_line!(
-                        Info,
-                        "midgame defense safe {defense_index} / {num_moves} num_solutions >= {num_solutions} {e}",
-                        num_solutions = solutions.len(),
-                    );
-                    return FullMove::Move(mov.mov);
-                }
-                log::write_line!(
-                    Info,
-                    "midgame defense {defense_index} / {num_moves} num_solutions = {num_solutions}",
-                    num_solutions = solutions.len()
-                );
-                match self.endgame_solver.solve(
-                    &solutions,
-                    Some(defense_deadline),
-                    defense_deadline_extended,
-                    settings::MIDGAME_DEFENSE_DIFFICULTY_MAX,
-                ) {
-                    Ok(EndgameResult::Loss) => {
-                        self.solutions = solutions;
-                        self.all_solutions_generated = true;
-                        log::write_line!(Info, "midgame win!");
-                        return if self.solutions.len() == 1 {
-                            FullMove::MoveClaimUnique(mov.mov)
-                        } else {
-                            FullMove::Move(mov.mov)
-                        };
-                    }
-                    Ok(EndgameResult::Win(maybe_move)) => {
-                        log::write_line!(Info, "midgame PANIC");
-                        if let Some(mov) = maybe_move {
-                            if mov.num_solutions > best_losing_move_difficulty {
-                                best_losing_move_index = defense_index;
-                                best_losing_move_difficulty = mov.num_solutions;
-                            }
-                        }
-                        let t = Instant::now();
-                        time_left =
-                            time_left.saturating_sub(t.saturating_duration_since(start_time));
-                        start_time = t;
-                    }
-                    Err(e) => {
-                        log::write_line!(Info, "safe {e}");
-                        self.solutions = solutions;
-                        self.all_solutions_generated = true;
-                        return FullMove::Move(mov.mov);
-                    }
-                }
-            } else {
-                log::write_line!(
-                    Info,
-                    "midgame num_solutions >= {num_solutions}",
-                    num_solutions = mov.num_solutions_lower_bound,
-                );
-                return FullMove::Move(mov.mov);
-            }
+            symmetry: Symmetry::identity(),
+            context: SearchContext::new(settings),
+            strategies,
         }
-        log::write_line!(
-            Info,
-            "midgame lost difficulty={best_losing_move_difficulty}"
-        );
-        FullMove::Move(moves[best_losing_move_index].mov)
     }
 }
 
@@ -170,7 +47,9 @@ impl Player for PlayerMain {
         match self.board.make_move(mov) {
             Ok(()) => {
                 if self.all_solutions_generated {
-                    self.solutions = self.solutions.filter(self.solutions.len(), mov);
+                    self.solutions = self
+                        .solutions
+                        .filter(self.solutions.len(), self.symmetry.forward_move(mov));
                     log::write_line!(Info, "opp move solutions: {}", self.solutions.len());
                 } else {
                     self.solutions = SolutionTable::empty();
@@ -183,21 +62,26 @@ impl Player for PlayerMain {
     }
 
     fn choose_move(&mut self, mut start_time: Instant, mut time_left: Duration) -> FullMove {
-        if !self.all_solutions_generated
-            && 81 - self.board.empty_squares().size() <= settings::OPENING_MAX_SQUARES
-        {
-            if let Some(mov) = self.choose_opening_move(start_time, time_left) {
+        if !self.all_solutions_generated {
+            if let Some(mov) = self.strategies.opening.choose_move(
+                &mut self.context,
+                &mut self.board,
+                start_time,
+                time_left,
+            ) {
                 self.board.make_move(mov).unwrap();
                 return FullMove::Move(mov);
             }
         }
         if !self.all_solutions_generated {
-            let (res, solutions) = SolutionTable::generate(
-                &self.board,
+            let board = self.normalize();
+            let (res, solutions) = SolutionTable::generate_with_ids(
+                &board,
                 settings::SOLUTIONS_MIN,
-                settings::SOLUTIONS_MAX,
-                start_time + time_left.mul_f64(settings::SOLUTION_GENERATE_TIME_FRACTION),
-                &mut self.rng,
+                self.context.settings.solutions_max,
+                start_time
+                    + time_left.mul_f64(self.context.settings.solution_generate_time_fraction),
+                self.context.solution_ids(),
             );
             self.solutions = solutions;
 
@@ -227,24 +111,123 @@ impl Player for PlayerMain {
         }
 
         if self.all_solutions_generated {
-            let mov =
-                self.endgame_solver
-                    .choose_move_best_effort(&self.solutions, start_time, time_left);
+            let mov = self.strategies.endgame.choose_move(
+                &mut self.context,
+                &self.solutions,
+                start_time,
+                time_left,
+            );
             if let Some(mov) = mov.to_move() {
-                self.board.make_move(mov).unwrap();
+                self.board
+                    .make_move(self.symmetry.backward_move(mov))
+                    .unwrap();
                 self.solutions = self.solutions.filter(self.solutions.len(), mov);
             }
-            mov
+            self.symmetry.backward_full_move(mov)
         } else {
-            let mov = self.midgame_choose_move_best_effort(start_time, time_left);
+            let mut board = self.symmetry.forward_board(&self.board);
+            let MidgameDecision { mov, solutions } = self.strategies.midgame.choose_move(
+                &mut self.context,
+                &mut board,
+                &self.solutions,
+                start_time,
+                time_left,
+            );
+            // Implied squares filled in by the strategy.
+            for square in Small::<81>::all() {
+                let Some(digit) = board.square(square).to_digit() else {
+                    continue;
+                };
+                let implied = self.symmetry.backward_move(Move { square, digit });
+                if self.board.empty_squares().contains(implied.square) {
+                    self.board.make_move(implied).unwrap();
+                }
+            }
             if let Some(mov) = mov.to_move() {
-                self.board.make_move(mov).unwrap();
-                // if all_solutions_generated, solutions already updated to next move by midgame_choose_move_best_effort
-                if !self.all_solutions_generated {
-                    self.solutions = SolutionTable::empty();
+                self.board
+                    .make_move(self.symmetry.backward_move(mov))
+                    .unwrap();
+            }
+            match solutions {
+                Some(solutions) => {
+                    self.solutions = solutions;
+                    self.all_solutions_generated = true;
                 }
+                None => self.solutions = SolutionTable::empty(),
+            }
+            self.symmetry.backward_full_move(mov)
+        }
+    }
+    fn ponder(&mut self, stop: &Arc<AtomicBool>) {
+        if !self.context.settings.ponder {
+            return;
+        }
+        let start_time = Instant::now();
+        if !self.all_solutions_generated {
+            self.ponder_generate(stop);
+        }
+        if self.all_solutions_generated {
+            self.ponder_endgame(stop);
+        }
+        log::write_line!(
+            Info,
+            "ponder time {:.3?}",
+            Instant::now().saturating_duration_since(start_time)
+        );
+    }
+}
+
+impl PlayerMain {
+    /// Chooses the frame of the solutions for the current board: normalized, or the board itself.
+    /// Returns the board in that frame.
+    fn normalize(&mut self) -> Board {
+        let (board, symmetry) = self.context.normalize(&self.board);
+        self.symmetry = symmetry;
+        board
+    }
+
+    /// Tries to generate all solutions for the current board.
+    fn ponder_generate(&mut self, stop: &AtomicBool) {
+        let board = self.normalize();
+        let (res, solutions) = SolutionTable::generate_until_stopped(
+            &board,
+            0,
+            self.context.settings.solutions_max,
+            stop,
+            self.context.solution_ids(),
+        );
+        match res {
+            Ok(()) => {
+                log::write_line!(Info, "ponder all solutions count={}", solutions.len());
+                self.solutions = solutions;
+                self.all_solutions_generated = true;
+            }
+            Err(e) => log::write_line!(Info, "ponder solutions {e}"),
+        }
+    }
+
+    /// Solves the positions after the opponent's replies, most likely first, into the
+    /// transposition table.
+    fn ponder_endgame(&mut self, stop: &Arc<AtomicBool>) {
+        if self.solutions.len() < 2 {
+            return;
+        }
+        let (solutions, mut moves) = self
+            .solutions
+            .compress_and_gen_moves(&self.solutions.move_tables());
+        // The opponent tries moves with the fewest solutions first.
+        moves.sort_by_key(|x| x.num_solutions);
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let endgame_solver = &mut self.context.endgame_solver;
+        endgame_solver.set_stop_flag(stop.clone());
+        let mut num_solved = 0;
+        for mov in &moves {
+            match endgame_solver.solve_after_move(&solutions, mov, None, deadline, None) {
+                Ok(_) => num_solved += 1,
+                Err(_) => break,
             }
-            mov
         }
+        endgame_solver.clear_stop_flag();
+        log::write_line!(Info, "ponder endgame solved {num_solved} / {}", moves.len());
     }
 }
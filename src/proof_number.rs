@@ -0,0 +1,337 @@
+use crate::{
+    board::FullMove,
+    endgame::{EndgameResult, EndgameSolver},
+    error::ResourcesExceeded,
+    log, settings,
+    solution_table::{EndgameMove, EndgameMoveNoHash, SolutionTable},
+    strategy::{BestEffortEndgame, EndgameStrategy, SearchContext},
+    transposition_table::EndgameTable,
+};
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+/// Proof or disproof number of a solved position.
+const INFINITY: u32 = u32::MAX;
+
+/// Depth-first proof-number search (df-pn).
+///
+/// Instead of searching moves in a fixed order like `EndgameSolver`, it always expands the
+/// position that looks easiest to prove or disprove. Positions with few solutions are left to
+/// `EndgameSolver`. The result is the same as from `EndgameSolver::solve`, but if there are
+/// several winning moves, a different one may be returned.
+pub struct ProofNumberSolver {
+    table: Vec<Bucket>,
+    index_mask: usize,
+    leaf_solutions: u32,
+    num_nodes: u64,
+}
+
+impl ProofNumberSolver {
+    /// Positions with at most `leaf_solutions` solutions are solved by `EndgameSolver`.
+    pub fn new(table_memory: usize, leaf_solutions: u32) -> Self {
+        let num_buckets = (table_memory / (2 * mem::size_of::<Bucket>()) + 1).next_power_of_two();
+        log::write_line!(
+            Info,
+            "proof number table {} MiB",
+            (num_buckets * mem::size_of::<Bucket>()) >> 20
+        );
+        Self {
+            table: vec![Bucket::new(); num_buckets],
+            index_mask: num_buckets - 1,
+            leaf_solutions,
+            num_nodes: 0,
+        }
+    }
+
+    pub fn num_nodes(&self) -> u64 {
+        self.num_nodes
+    }
+
+    /// `endgame_solver` solves the leaf positions.
+    pub fn solve<T: EndgameTable>(
+        &mut self,
+        endgame_solver: &mut EndgameSolver<T>,
+        solutions: &SolutionTable,
+        deadline: Instant,
+    ) -> Result<EndgameResult, ResourcesExceeded> {
+        let start_time = Instant::now();
+        self.num_nodes = 0;
+        endgame_solver.transposition_table_mut().new_era();
+
+        if solutions.is_empty() {
+            log::write_line!(Always, "Error: no solutions!");
+            return Ok(EndgameResult::Loss);
+        }
+        if solutions.len() == 1 {
+            return Ok(EndgameResult::Loss);
+        }
+        if solutions.len() < 4 {
+            return Ok(EndgameResult::Win(None));
+        }
+
+        // With infinite thresholds the search only returns when the position is solved.
+        let (numbers, best_move) =
+            self.search(endgame_solver, solutions, INFINITY, INFINITY, deadline)?;
+        let result = if numbers.proof == 0 {
+            EndgameResult::Win(Some(best_move))
+        } else {
+            assert_eq!(numbers.disproof, 0);
+            EndgameResult::Loss
+        };
+
+        let processing_time = Instant::now().saturating_duration_since(start_time);
+        log::write_line!(
+            Info,
+            "pn nodes: {} endgame nodes: {} time: {:.3?} knps: {:.1}",
+            self.num_nodes,
+            endgame_solver.num_nodes(),
+            processing_time,
+            self.num_nodes as f64 / processing_time.as_secs_f64() / 1000.0
+        );
+        Ok(result)
+    }
+
+    /// Searches until the proof number reaches `proof_max` or the disproof number reaches
+    /// `disproof_max`.
+    ///
+    /// Returns the numbers and the move with the smallest disproof number (the winning move if
+    /// the position is proven).
+    fn search<T: EndgameTable>(
+        &mut self,
+        endgame_solver: &mut EndgameSolver<T>,
+        solutions: &SolutionTable,
+        proof_max: u32,
+        disproof_max: u32,
+        deadline: Instant,
+    ) -> Result<(ProofNumbers, EndgameMoveNoHash), ResourcesExceeded> {
+        self.num_nodes += 1;
+        let start_nodes = self.num_nodes;
+
+        if self.num_nodes % settings::ENDGAME_CHECK_TIME_NODES == 0 && Instant::now() >= deadline {
+            return Err(ResourcesExceeded::Time);
+        }
+
+        let (solutions, mut moves) = solutions.compress_and_gen_moves(&solutions.move_tables());
+        moves.sort_by_key(|x| x.num_solutions);
+        // Numbers from the point of view of the opponent after each move.
+        let mut children: Vec<ProofNumbers> =
+            moves.iter().map(|mov| self.child_numbers(mov)).collect();
+
+        loop {
+            // We win if any move wins, we lose if all moves lose.
+            let mut best_index = 0;
+            let mut second_disproof = INFINITY;
+            let mut disproof_sum: u32 = 0;
+            for (index, child) in children.iter().enumerate() {
+                if child.disproof < children[best_index].disproof {
+                    second_disproof = children[best_index].disproof;
+                    best_index = index;
+                } else if index != best_index && child.disproof < second_disproof {
+                    second_disproof = child.disproof;
+                }
+                disproof_sum = disproof_sum.saturating_add(child.proof);
+            }
+            let proof = children[best_index].disproof;
+            let disproof = if proof == 0 {
+                INFINITY
+            } else {
+                // Only a solved position has infinite numbers.
+                disproof_sum.min(INFINITY - 1)
+            };
+            let numbers = ProofNumbers { proof, disproof };
+
+            let best = &moves[best_index];
+            if proof >= proof_max || disproof >= disproof_max {
+                let work = (self.num_nodes - start_nodes)
+                    .try_into()
+                    .unwrap_or(u32::MAX);
+                self.insert(solutions.hash(), numbers, work);
+                let best_move = EndgameMoveNoHash {
+                    mov: solutions.original_move(best.mov),
+                    num_solutions: best.num_solutions,
+                };
+                return Ok((numbers, best_move));
+            }
+
+            children[best_index] = if best.num_solutions <= self.leaf_solutions {
+                match endgame_solver.solve_after_move(&solutions, best, None, deadline, None)? {
+                    EndgameResult::Win(_) => ProofNumbers {
+                        proof: 0,
+                        disproof: INFINITY,
+                    },
+                    EndgameResult::Loss => ProofNumbers {
+                        proof: INFINITY,
+                        disproof: 0,
+                    },
+                }
+            } else {
+                let child = children[best_index];
+                let child_proof_max = (disproof_max - disproof).saturating_add(child.proof);
+                // 1 + epsilon trick: stay in the child a bit longer to avoid switching back and
+                // forth.
+                let child_disproof_max = proof_max.min(
+                    second_disproof
+                        .saturating_add(second_disproof / 4)
+                        .saturating_add(1),
+                );
+                let new_solutions = solutions.filter(best.num_solutions, best.mov);
+                let (child, _) = self.search(
+                    endgame_solver,
+                    &new_solutions,
+                    child_proof_max,
+                    child_disproof_max,
+                    deadline,
+                )?;
+                child
+            };
+        }
+    }
+
+    fn child_numbers(&self, mov: &EndgameMove) -> ProofNumbers {
+        if mov.num_solutions == 1 {
+            // The opponent lost.
+            return ProofNumbers {
+                proof: INFINITY,
+                disproof: 0,
+            };
+        }
+        if mov.num_solutions < 4 {
+            // The opponent wins with a move leaving 1 solution.
+            return ProofNumbers {
+                proof: 0,
+                disproof: INFINITY,
+            };
+        }
+        self.find(mov.hash).unwrap_or(ProofNumbers {
+            proof: 1,
+            disproof: 1,
+        })
+    }
+
+    fn find(&self, hash: u64) -> Option<ProofNumbers> {
+        // Safety: index_mask guarantees the index is in range.
+        let bucket = unsafe { self.table.get_unchecked((hash as usize) & self.index_mask) };
+        bucket
+            .entries
+            .iter()
+            .find(|entry| entry.hash == hash)
+            .map(|entry| entry.numbers)
+    }
+
+    fn insert(&mut self, hash: u64, numbers: ProofNumbers, work: u32) {
+        // Safety: index_mask guarantees the index is in range.
+        let bucket = unsafe {
+            self.table
+                .get_unchecked_mut((hash as usize) & self.index_mask)
+        };
+        // Replace the same position, or else the one that took the least work.
+        let entry = bucket
+            .entries
+            .iter_mut()
+            .min_by_key(|entry| (entry.hash != hash, entry.work))
+            .unwrap();
+        *entry = Entry {
+            hash,
+            numbers,
+            work,
+        };
+    }
+}
+
+/// Proof number: how many positions need to be solved to prove a win for the side to move.
+/// Disproof number: same for a loss.
+#[derive(Copy, Clone, Debug)]
+struct ProofNumbers {
+    proof: u32,
+    disproof: u32,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Entry {
+    hash: u64,
+    numbers: ProofNumbers,
+    /// Number of nodes searched to get the numbers.
+    work: u32,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Bucket {
+    entries: [Entry; 4],
+}
+
+impl Bucket {
+    fn new() -> Self {
+        Self {
+            entries: [Entry {
+                hash: 0,
+                numbers: ProofNumbers {
+                    proof: 1,
+                    disproof: 1,
+                },
+                work: 0,
+            }; 4],
+        }
+    }
+}
+
+/// Proof-number search within `proof_number_time_fraction`. If it finds no win, the defense of
+/// `BestEffortEndgame` if the position is lost, otherwise all of it.
+pub struct ProofNumberEndgame {
+    /// Created on first use, with the table size from the settings.
+    proof_number_solver: Option<ProofNumberSolver>,
+}
+
+impl ProofNumberEndgame {
+    pub fn new() -> Self {
+        Self {
+            proof_number_solver: None,
+        }
+    }
+}
+
+impl EndgameStrategy for ProofNumberEndgame {
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        solutions: &SolutionTable,
+        mut start_time: Instant,
+        mut time_left: Duration,
+    ) -> FullMove {
+        let deadline = start_time + time_left.mul_f64(context.settings.proof_number_time_fraction);
+        let settings = &context.settings;
+        let proof_number_solver = self.proof_number_solver.get_or_insert_with(|| {
+            ProofNumberSolver::new(
+                settings.proof_number_table_memory,
+                settings.proof_number_leaf_solutions,
+            )
+        });
+        let result = proof_number_solver.solve(&mut context.endgame_solver, solutions, deadline);
+        let t = Instant::now();
+        time_left = time_left.saturating_sub(t.saturating_duration_since(start_time));
+        start_time = t;
+        match result {
+            Ok(EndgameResult::Win(Some(mov))) => {
+                log::write_line!(Info, "proof number win difficulty={}", mov.num_solutions);
+                return if mov.num_solutions == 1 {
+                    FullMove::MoveClaimUnique(mov.mov)
+                } else {
+                    FullMove::Move(mov.mov)
+                };
+            }
+            Ok(EndgameResult::Loss) => {
+                log::write_line!(Info, "proof number loss");
+                return context.endgame_solver.choose_move_defense(
+                    &context.settings,
+                    solutions,
+                    start_time,
+                    time_left,
+                );
+            }
+            Ok(result) => log::write_line!(Info, "proof number {result:?}"),
+            Err(e) => log::write_line!(Info, "proof number {e}"),
+        }
+        BestEffortEndgame.choose_move(context, solutions, start_time, time_left)
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{
+    board::Move,
+    digit::Digit,
+    endgame::{EndgameResult, EndgameSolver},
+    error::{InvalidInput, ResourcesExceeded},
+    small::Small,
+    solution_table::{EndgameMoveNoHash, SolutionTable},
+    transposition_table::EndgameTable,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    iter::Peekable,
+    str::FromStr,
+    time::Instant,
+};
+
+/// Proof of an endgame result. Moves are in original coordinates.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProofTree {
+    /// The side to move wins with this move.
+    Win(Box<ProofMove>),
+    /// The side to move loses: every move is refuted. Empty if the solution is already unique.
+    Loss(Vec<ProofMove>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofMove {
+    pub mov: Move,
+    /// Number of solutions after the move.
+    pub num_solutions: u32,
+    /// The position after the move.
+    pub tree: ProofTree,
+}
+
+impl ProofTree {
+    pub fn is_win(&self) -> bool {
+        matches!(self, Self::Win(_))
+    }
+
+    /// Number of moves in the tree.
+    pub fn num_moves(&self) -> usize {
+        match self {
+            Self::Win(proof_move) => 1 + proof_move.tree.num_moves(),
+            Self::Loss(refutations) => refutations.iter().map(|x| 1 + x.tree.num_moves()).sum(),
+        }
+    }
+
+    fn write_moves(&self, f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+        let moves = match self {
+            Self::Win(proof_move) => std::slice::from_ref(&**proof_move),
+            Self::Loss(refutations) => &refutations[..],
+        };
+        for proof_move in moves {
+            writeln!(
+                f,
+                "{:indent$}{} {}",
+                "",
+                proof_move.mov,
+                proof_move.num_solutions,
+                indent = 2 * depth
+            )?;
+            proof_move.tree.write_moves(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Format: "win" or "loss", followed by a line per move: the move and the number of solutions
+/// after it, indented by 2 spaces per ply.
+impl Display for ProofTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", if self.is_win() { "win" } else { "loss" })?;
+        self.write_moves(f, 0)
+    }
+}
+
+impl FromStr for ProofTree {
+    type Err = InvalidInput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let mut lines = s.lines();
+        let is_win = match lines.next() {
+            Some("win") => true,
+            Some("loss") => false,
+            _ => return Err(InvalidInput),
+        };
+        let mut move_lines = lines.map(parse_move_line).peekable();
+        let tree = parse_moves(&mut move_lines, 0, is_win)?;
+        if move_lines.next().is_some() {
+            return Err(InvalidInput);
+        }
+        Ok(tree)
+    }
+}
+
+type MoveLine = Result<(usize, Move, u32), InvalidInput>;
+
+fn parse_move_line(line: &str) -> MoveLine {
+    let trimmed = line.trim_start_matches(' ');
+    let indent = line.len() - trimmed.len();
+    if indent % 2 != 0 {
+        return Err(InvalidInput);
+    }
+    let mut words = trimmed.split(' ');
+    let mov = words.next().ok_or(InvalidInput)?.parse()?;
+    let num_solutions = words
+        .next()
+        .ok_or(InvalidInput)?
+        .parse()
+        .map_err(|_| InvalidInput)?;
+    if words.next().is_some() {
+        return Err(InvalidInput);
+    }
+    Ok((indent / 2, mov, num_solutions))
+}
+
+fn parse_moves<I: Iterator<Item = MoveLine>>(
+    lines: &mut Peekable<I>,
+    depth: usize,
+    is_win: bool,
+) -> Result<ProofTree, InvalidInput> {
+    let mut moves = Vec::new();
+    while let Some(line) = lines.peek() {
+        let &(line_depth, mov, num_solutions) = line.as_ref().map_err(|&e| e)?;
+        if line_depth < depth {
+            break;
+        }
+        if line_depth > depth {
+            return Err(InvalidInput);
+        }
+        lines.next();
+        let tree = parse_moves(lines, depth + 1, !is_win)?;
+        moves.push(ProofMove {
+            mov,
+            num_solutions,
+            tree,
+        });
+    }
+    if is_win {
+        if moves.len() != 1 {
+            return Err(InvalidInput);
+        }
+        Ok(ProofTree::Win(Box::new(moves.pop().unwrap())))
+    } else {
+        Ok(ProofTree::Loss(moves))
+    }
+}
+
+/// A legal move in a `SolutionTable`.
+struct Candidate {
+    /// In the table's coordinates.
+    local: Move,
+    original: Move,
+    num_solutions: u32,
+}
+
+/// Sorted by number of solutions.
+fn candidate_moves(solutions: &SolutionTable) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for (square, move_table) in Small::all().zip(solutions.move_tables().iter()) {
+        for (digit, &num_solutions) in Digit::all().zip(move_table.num_solutions.iter()) {
+            if num_solutions != 0 && num_solutions != solutions.len() {
+                let local = Move { square, digit };
+                candidates.push(Candidate {
+                    local,
+                    original: solutions.original_move(local),
+                    num_solutions,
+                });
+            }
+        }
+    }
+    candidates.sort_by_key(|x| x.num_solutions);
+    candidates
+}
+
+/// Extraction of proofs. Call after `solve` to reuse its transposition table results.
+impl<T: EndgameTable> EndgameSolver<T> {
+    /// The winning side's moves and the losing side's longest resistance (by number of
+    /// solutions), until the solution is unique.
+    pub fn principal_variation(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline: Instant,
+    ) -> Result<Vec<EndgameMoveNoHash>, ResourcesExceeded> {
+        let mut variation = Vec::new();
+        let mut current: Option<SolutionTable> = None;
+        loop {
+            let position = current.as_ref().unwrap_or(solutions);
+            let candidate = match self.solve_position(position, None, deadline, None)? {
+                EndgameResult::Win(_) => self.winning_move(position, deadline)?,
+                EndgameResult::Loss => match candidate_moves(position).pop() {
+                    Some(candidate) => candidate,
+                    None => break,
+                },
+            };
+            variation.push(EndgameMoveNoHash {
+                mov: candidate.original,
+                num_solutions: candidate.num_solutions,
+            });
+            let next = position.filter(candidate.num_solutions, candidate.local);
+            current = Some(next);
+        }
+        Ok(variation)
+    }
+
+    /// A winning move for the winning side and all refutations for the losing side.
+    pub fn proof_tree(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline: Instant,
+    ) -> Result<ProofTree, ResourcesExceeded> {
+        match self.solve_position(solutions, None, deadline, None)? {
+            EndgameResult::Win(_) => {
+                let candidate = self.winning_move(solutions, deadline)?;
+                let new_solutions = solutions.filter(candidate.num_solutions, candidate.local);
+                let tree = self.proof_tree(&new_solutions, deadline)?;
+                Ok(ProofTree::Win(Box::new(ProofMove {
+                    mov: candidate.original,
+                    num_solutions: candidate.num_solutions,
+                    tree,
+                })))
+            }
+            EndgameResult::Loss => {
+                let mut refutations = Vec::new();
+                for candidate in candidate_moves(solutions) {
+                    let new_solutions = solutions.filter(candidate.num_solutions, candidate.local);
+                    let tree = self.proof_tree(&new_solutions, deadline)?;
+                    assert!(tree.is_win(), "inconsistent endgame result");
+                    refutations.push(ProofMove {
+                        mov: candidate.original,
+                        num_solutions: candidate.num_solutions,
+                        tree,
+                    });
+                }
+                Ok(ProofTree::Loss(refutations))
+            }
+        }
+    }
+
+    /// Requires a won position.
+    fn winning_move(
+        &mut self,
+        solutions: &SolutionTable,
+        deadline: Instant,
+    ) -> Result<Candidate, ResourcesExceeded> {
+        let mut candidates = candidate_moves(solutions);
+        let mut index = candidates.iter().position(|x| x.num_solutions == 1);
+        if index.is_none() {
+            if let Some(EndgameResult::Win(Some(mov))) =
+                self.transposition_table().find(solutions.hash())
+            {
+                index = candidates.iter().position(|x| x.original == mov.mov);
+            }
+        }
+        if index.is_none() {
+            for (i, candidate) in candidates.iter().enumerate() {
+                let new_solutions = solutions.filter(candidate.num_solutions, candidate.local);
+                if self.solve_position(&new_solutions, None, deadline, None)? == EndgameResult::Loss
+                {
+                    index = Some(i);
+                    break;
+                }
+            }
+        }
+        let index = index.expect("inconsistent endgame result");
+        Ok(candidates.swap_remove(index))
+    }
+}
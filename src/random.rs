@@ -25,10 +25,10 @@ impl RandomGenerator {
             chacha20_counter: 0,
             block: [0u8; 64],
             block_index: 64,
-            /// Always uniformly random in 0..2^num_bits.
+            // Always uniformly random in 0..2^num_bits.
             bits: 0,
             num_bits: 0,
-            /// Always uniformly random in 0..number_range.
+            // Always uniformly random in 0..number_range.
             number: 0,
             number_range: 1,
         }
@@ -0,0 +1,163 @@
+use crate::{
+    board::{Board, FullMove, Move},
+    digit_set::DigitSet,
+    fast_solver::FastSolver,
+    game_record::GameResult,
+    solver::{Solver, SolverStep},
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// Enforces the rules of the game.
+///
+/// A move must fill an empty square, keep the sudoku solvable, and not be implied (the digit must
+/// not be forced by the other squares). A claim is correct if exactly one solution is left.
+pub struct Referee {
+    board: Board,
+    moves: Vec<FullMove>,
+    result: Option<GameResult>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IllegalMove {
+    /// The square is already filled.
+    Occupied,
+    /// No solutions after the move.
+    Unsolvable,
+    /// All solutions have this digit in the square.
+    Implied,
+    /// More than one solution after the claim.
+    FalseClaim,
+}
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Occupied => write!(f, "square occupied"),
+            Self::Unsolvable => write!(f, "no solutions"),
+            Self::Implied => write!(f, "implied move"),
+            Self::FalseClaim => write!(f, "false claim"),
+        }
+    }
+}
+
+impl Error for IllegalMove {}
+
+impl Referee {
+    pub fn new() -> Self {
+        Self::with_board(Board::new())
+    }
+
+    /// The game starts from `board`, which must have a solution.
+    pub fn with_board(board: Board) -> Self {
+        Self {
+            board,
+            moves: Vec::new(),
+            result: None,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn moves(&self) -> &[FullMove] {
+        &self.moves
+    }
+
+    /// The player to move: 0 moves first.
+    pub fn turn(&self) -> usize {
+        self.moves.len() % 2
+    }
+
+    /// None while the game is in progress.
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// Plays a move for the player to move.
+    ///
+    /// An illegal move is not played, and the opponent wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game is over.
+    pub fn make_move(&mut self, mov: FullMove) -> Result<(), IllegalMove> {
+        assert!(self.result.is_none());
+        let res = self.check_move(mov);
+        let turn = self.turn();
+        match res {
+            Ok(()) => {
+                if let Some(mov) = mov.to_move() {
+                    self.board.make_move(mov).unwrap();
+                }
+                self.moves.push(mov);
+                if !matches!(mov, FullMove::Move(_)) {
+                    // A correct claim.
+                    self.result = Some(GameResult {
+                        winner: turn,
+                        failure: false,
+                    });
+                }
+            }
+            Err(_) => self.forfeit(),
+        }
+        res
+    }
+
+    /// The player to move loses by failure, e.g. because of a crash or running out of time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game is over.
+    pub fn forfeit(&mut self) {
+        assert!(self.result.is_none());
+        self.result = Some(GameResult {
+            winner: self.turn() ^ 1,
+            failure: true,
+        });
+    }
+
+    fn check_move(&self, mov: FullMove) -> Result<(), IllegalMove> {
+        let mut board = self.board;
+        if let Some(mov) = mov.to_move() {
+            if board.make_move(mov).is_err() {
+                return Err(IllegalMove::Occupied);
+            }
+            if count_solutions(&self.board, 1, Some(mov)) == 0 {
+                return Err(IllegalMove::Implied);
+            }
+        }
+        match count_solutions(&board, 2, None) {
+            0 => Err(IllegalMove::Unsolvable),
+            1 => Ok(()),
+            _ => match mov {
+                FullMove::Move(_) => Ok(()),
+                FullMove::MoveClaimUnique(_) | FullMove::ClaimUnique => {
+                    Err(IllegalMove::FalseClaim)
+                }
+            },
+        }
+    }
+}
+
+/// Counts solutions up to `max_solutions`, not counting solutions with the `except` move.
+fn count_solutions(board: &Board, max_solutions: u32, except: Option<Move>) -> u32 {
+    let mut solutions = 0;
+    let mut solver = FastSolver::new(board);
+    if let Some(mov) = except {
+        solver.remove_possibilities(mov.square, DigitSet::only(mov.digit));
+    }
+    while solutions < max_solutions {
+        match solver.step() {
+            SolverStep::NoProgress => {}
+            SolverStep::Found(_) => {
+                solutions += 1;
+            }
+            SolverStep::Done => break,
+        }
+    }
+    solutions
+}
@@ -1,34 +1,219 @@
-use std::time::Duration;
+use crate::evaluation::EvaluationModel; // submission::skip
+use crate::{error::InvalidInput, strategy::StrategySpec};
+use std::{
+    env,
+    fmt::{self, Display, Formatter},
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    time::Duration,
+};
+
+// Fixed parameters.
+pub const SOLUTION_GENERATE_CHECK_TIME_ITERS: u64 = 1024;
+pub const SOLUTIONS_MIN: u32 = 2;
+pub const ENDGAME_CHECK_TIME_NODES: u64 = 1024;
 
 #[cfg(feature = "tomeks_computer")]
-pub const GAME_TIME_LIMIT: Duration = Duration::from_millis(10_000);
+const GAME_TIME_LIMIT: Duration = Duration::from_millis(10_000);
 #[cfg(not(feature = "tomeks_computer"))]
-pub const GAME_TIME_LIMIT: Duration = Duration::from_millis(29_800);
+const GAME_TIME_LIMIT: Duration = Duration::from_millis(29_800);
 
-// Time allocation.
-pub const OPENING_MOVEGEN_TIME_FRACTION: f64 = 0.1;
-pub const MIDGAME_MOVEGEN_TIME_FRACTION: f64 = 0.1;
-pub const SOLUTION_GENERATE_TIME_FRACTION: f64 = 0.2;
+const ENV_PREFIX: &str = "NINEDIGITS_";
 
-pub const MIDGAME_DEFENSE_TIME_FRACTION: f64 = 0.2;
-pub const MIDGAME_DEFENSE_EXTENDED_TIME_FRACTION: f64 = 0.25;
-pub const ENDGAME_OFFENSE_TIME_FRACTION: f64 = 0.33;
-pub const ENDGAME_OFFENSE_EXTENDED_TIME_FRACTION: f64 = 0.48;
-pub const ENDGAME_DEFENSE_TIME_FRACTION: f64 = 0.2;
-pub const ENDGAME_DEFENSE_EXTENDED_TIME_FRACTION: f64 = 0.30;
+macro_rules! define_settings {
+    ($($(#[$attr:meta])* $name:ident: $type:ty = $default:expr,)*) => {
+        /// Tuning parameters.
+        ///
+        /// Text format: `name = value` lines, `#` starts a comment. Durations are in
+        /// milliseconds, missing optional values are `none`.
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Settings {
+            $($(#[$attr])* pub $name: $type,)*
+        }
 
-// Opening.
-pub const OPENING_MAX_SQUARES: u8 = 14;
+        impl Settings {
+            /// Default values.
+            pub fn new() -> Self {
+                Self {
+                    $($name: $default,)*
+                }
+            }
 
-// Midgame.
-pub const SOLUTION_GENERATE_CHECK_TIME_ITERS: u64 = 1024;
-pub const SOLUTIONS_MIN: u32 = 2;
-pub const SOLUTIONS_MAX: u32 = 100_000;
-pub const MIDGAME_DEFENSE_SOLUTIONS_MAX: u32 = 90_000;
-pub const MIDGAME_DEFENSE_DIFFICULTY_MAX: Option<u32> = Some(4500);
+            pub fn set(&mut self, name: &str, value: &str) -> Result<(), InvalidInput> {
+                match name {
+                    $(stringify!($name) => self.$name = SettingValue::parse(value)?,)*
+                    _ => return Err(InvalidInput),
+                }
+                Ok(())
+            }
 
-// Endgame.
-pub const ENDGAME_CHECK_TIME_NODES: u64 = 1024;
-pub const TRANSPOSITION_TABLE_MEMORY: usize = 512 << 20;
-pub const ENDGAME_OFFENSE_DIFFICULTY_MAX: Option<u32> = Some(11000);
-pub const ENDGAME_DEFENSE_DIFFICULTY_MAX: Option<u32> = Some(4000);
+            /// Applies environment variables such as `NINEDIGITS_SOLUTIONS_MAX`.
+            pub fn load_env(&mut self) -> Result<(), InvalidInput> {
+                $(
+                    let var = format!("{ENV_PREFIX}{}", stringify!($name).to_uppercase());
+                    if let Ok(value) = env::var(var) {
+                        self.$name = SettingValue::parse(value.trim())?;
+                    }
+                )*
+                Ok(())
+            }
+        }
+
+        impl Display for Settings {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                $(
+                    write!(f, "{} = ", stringify!($name))?;
+                    self.$name.write(f)?;
+                    writeln!(f)?;
+                )*
+                Ok(())
+            }
+        }
+    };
+}
+
+define_settings! {
+    game_time_limit: Duration = GAME_TIME_LIMIT,
+    /// Think while waiting for the opponent's move.
+    ponder: bool = false,
+    /// Strategy for each phase, e.g. `opening=none:endgame=proof-number`. Phases are separated by
+    /// `:`, so that they fit in a comma-separated list of settings.
+    strategies: StrategySpec = StrategySpec::default(),
+
+    // Time allocation.
+    opening_movegen_time_fraction: f64 = 0.1,
+    midgame_movegen_time_fraction: f64 = 0.1,
+    solution_generate_time_fraction: f64 = 0.2,
+    midgame_defense_time_fraction: f64 = 0.2,
+    midgame_defense_extended_time_fraction: f64 = 0.25,
+    mcts_time_fraction: f64 = 0.3,
+    endgame_offense_time_fraction: f64 = 0.33,
+    endgame_offense_extended_time_fraction: f64 = 0.48,
+    endgame_defense_time_fraction: f64 = 0.2,
+    endgame_defense_extended_time_fraction: f64 = 0.30,
+    endgame_distance_time_fraction: f64 = 0.15,
+    proof_number_time_fraction: f64 = 0.33,
+
+    // Opening.
+    opening_max_squares: u8 = 14,
+
+    // Midgame.
+    solutions_max: u32 = 100_000,
+    /// Derive solution IDs from content, so that transposition table results carry over between
+    /// moves.
+    content_solution_ids: bool = true,
+    /// Generate solutions of normalized boards, so that transposition table results carry over
+    /// between symmetric positions. Off until shown to pay for the normalization time.
+    normalize_positions: bool = false,
+    midgame_defense_solutions_max: u32 = 90_000,
+    midgame_defense_difficulty_max: Option<u32> = Some(4500),
+    #[doc = "Weights for `midgame=evaluation` (experimental), separated by `:`."] // submission::skip
+    evaluation_model: EvaluationModel = EvaluationModel::new(), // submission::skip
+    /// `midgame=evaluation`: random probes for the solution estimate of each move.
+    evaluation_estimate_probes: u32 = 100,
+    /// `midgame=mcts`: positions with at most this many solutions are solved exactly.
+    mcts_leaf_solutions: u32 = 1000,
+    /// `midgame=mcts`: number of moves with the most solutions searched in each position.
+    mcts_moves_max: usize = 30,
+    /// `midgame=mcts`: UCT exploration constant.
+    mcts_exploration: f64 = 0.7,
+    /// `midgame=mcts`: stop after this many iterations, for searches that don't depend on the
+    /// speed of the machine.
+    mcts_iterations_max: Option<u64> = None,
+
+    // Endgame.
+    transposition_table_memory: usize = 512 << 20,
+    /// Search for the fastest win / longest resistance first, within
+    /// `endgame_distance_time_fraction`. The normal search gets the remaining time.
+    endgame_distance: bool = false,
+    endgame_offense_difficulty_max: Option<u32> = Some(11000),
+    endgame_defense_difficulty_max: Option<u32> = Some(4000),
+    proof_number_table_memory: usize = 128 << 20,
+    /// Smaller positions are solved by the depth-first endgame solver.
+    proof_number_leaf_solutions: u32 = 300,
+}
+
+impl Settings {
+    /// Applies comma-separated `name=value` pairs, e.g. `solutions_max=50000,endgame_distance=true`.
+    pub fn set_all(&mut self, s: &str) -> Result<(), InvalidInput> {
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (name, value) = item.split_once('=').ok_or(InvalidInput)?;
+            self.set(name.trim(), value.trim())?;
+        }
+        Ok(())
+    }
+
+    /// Applies settings in the text format.
+    pub fn load_str(&mut self, s: &str) -> Result<(), InvalidInput> {
+        for line in s.lines() {
+            let line = match line.split_once('#') {
+                Some((line, _comment)) => line,
+                None => line,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (name, value) = line.split_once('=').ok_or(InvalidInput)?;
+            self.set(name.trim(), value.trim())?;
+        }
+        Ok(())
+    }
+
+    pub fn load_file(&mut self, path: &Path) -> io::Result<()> {
+        let s = fs::read_to_string(path)?;
+        self.load_str(&s)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+trait SettingValue: Sized {
+    fn parse(s: &str) -> Result<Self, InvalidInput>;
+    fn write(&self, f: &mut Formatter<'_>) -> fmt::Result;
+}
+
+macro_rules! impl_setting_value_from_str {
+    ($($type:ty),*) => {
+        $(
+            impl SettingValue for $type {
+                fn parse(s: &str) -> Result<Self, InvalidInput> {
+                    s.parse().map_err(|_| InvalidInput)
+                }
+
+                fn write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                    write!(f, "{self}")
+                }
+            }
+        )*
+    };
+}
+
+impl_setting_value_from_str!(bool, u8, u32, u64, usize, f64, StrategySpec);
+impl_setting_value_from_str!(EvaluationModel); // submission::skip
+
+impl SettingValue for Duration {
+    fn parse(s: &str) -> Result<Self, InvalidInput> {
+        Ok(Duration::from_millis(u64::parse(s)?))
+    }
+
+    fn write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_millis())
+    }
+}
+
+impl<T: SettingValue> SettingValue for Option<T> {
+    fn parse(s: &str) -> Result<Self, InvalidInput> {
+        if s == "none" {
+            Ok(None)
+        } else {
+            T::parse(s).map(Some)
+        }
+    }
+
+    fn write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            None => write!(f, "none"),
+            Some(value) => value.write(f),
+        }
+    }
+}
@@ -7,7 +7,6 @@ use std::{
 /// A number in range 0..L.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[repr(transparent)]
-
 pub struct Small<const L: usize>(u8);
 
 impl<const L: usize> Small<L> {
@@ -0,0 +1,107 @@
+//! Estimates the number of solutions of boards with too many solutions to generate.
+//!
+//! Knuth's estimator: a random path down the search tree of `BasicSolver`. The product of the
+//! branching factors along the path, or 0 at a dead end, is an unbiased estimate of the number of
+//! leaves that are solutions.
+
+use crate::{
+    basic_solver::SearchState,
+    board::{Board, Move},
+    random::RandomGenerator,
+    small::Small,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolutionEstimate {
+    pub estimate: f64,
+    /// Standard error of the estimate.
+    pub std_error: f64,
+    pub probes: u32,
+}
+
+impl SolutionEstimate {
+    /// Lower bound of a 95% confidence interval.
+    pub fn lower(&self) -> f64 {
+        (self.estimate - 1.96 * self.std_error).max(0.0)
+    }
+
+    /// Upper bound of a 95% confidence interval.
+    pub fn upper(&self) -> f64 {
+        self.estimate + 1.96 * self.std_error
+    }
+}
+
+/// Averages `probes` random probes.
+///
+/// # Panics
+///
+/// Panics if `probes` is 0.
+pub fn estimate_solutions(
+    board: &Board,
+    probes: u32,
+    rng: &mut RandomGenerator,
+) -> SolutionEstimate {
+    assert!(probes > 0);
+    let mut state = SearchState::new();
+    for square in Small::<81>::all() {
+        if let Some(digit) = board.square(square).to_digit() {
+            if !state.possibilities(square).contains(digit) {
+                return SolutionEstimate {
+                    estimate: 0.0,
+                    std_error: 0.0,
+                    probes,
+                };
+            }
+            state.make_move(Move { square, digit });
+        }
+    }
+    let mut sum = 0.0;
+    let mut sum_squares = 0.0;
+    for _ in 0..probes {
+        let x = probe(state, rng);
+        sum += x;
+        sum_squares += x * x;
+    }
+    let n = f64::from(probes);
+    let estimate = sum / n;
+    let variance = if probes > 1 {
+        ((sum_squares - n * estimate * estimate) / (n - 1.0)).max(0.0)
+    } else {
+        0.0
+    };
+    SolutionEstimate {
+        estimate,
+        std_error: (variance / n).sqrt(),
+        probes,
+    }
+}
+
+fn probe(mut state: SearchState, rng: &mut RandomGenerator) -> f64 {
+    let mut weight = 1.0;
+    loop {
+        // The square with the fewest possibilities.
+        let mut branch = None;
+        for square in state.board.empty_squares() {
+            let possibilities = state.possibilities(square);
+            let size = possibilities.size();
+            if size == 0 {
+                return 0.0;
+            }
+            if branch.map_or(true, |(_, best_size, _)| size < best_size) {
+                branch = Some((square, size, possibilities));
+                if size == 1 {
+                    break;
+                }
+            }
+        }
+        let Some((square, size, possibilities)) = branch else {
+            return weight;
+        };
+        weight *= f64::from(size);
+        let digit = possibilities
+            .into_iter()
+            .nth(rng.uniform_usize(usize::from(size)))
+            .unwrap();
+        state.make_move(Move { square, digit });
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    board::{Board, Move},
+    board::{Board, FilledBoard, Move},
     digit::Digit,
     error::ResourcesExceeded,
     fast_solver::FastSolver,
@@ -7,8 +7,13 @@ use crate::{
     settings,
     small::Small,
     solver::{Solver, SolverStep},
+    symmetry::Symmetry,
+};
+use std::{
+    slice,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
 };
-use std::{slice, time::Instant};
 
 pub struct SolutionTable {
     original_squares: Vec<Small<81>>,
@@ -54,6 +59,13 @@ impl SolutionTable {
         self.hash
     }
 
+    /// Xor of the solution IDs rehashed with `key`: a fingerprint of the set of solutions
+    /// independent of `hash`.
+    pub fn keyed_hash(&self, key: u64) -> u64 {
+        self.iter()
+            .fold(0, |hash, solution| hash ^ mix64(key ^ solution.id()))
+    }
+
     pub fn num_squares(&self) -> u8 {
         self.original_squares.len() as u8
     }
@@ -90,6 +102,40 @@ impl SolutionTable {
         max: u32,
         deadline: Instant,
         rng: &mut RandomGenerator,
+    ) -> (Result<(), ResourcesExceeded>, Self) {
+        Self::generate_with_ids(board, min, max, deadline, SolutionIds::Random(rng))
+    }
+
+    /// Like `generate`, with a choice of how solution IDs are assigned.
+    pub fn generate_with_ids(
+        board: &Board,
+        min: u32,
+        max: u32,
+        deadline: Instant,
+        ids: SolutionIds,
+    ) -> (Result<(), ResourcesExceeded>, Self) {
+        Self::generate_until(board, min, max, Some(deadline), None, ids)
+    }
+
+    /// Like `generate_with_ids`, but instead of a deadline, `ResourcesExceeded::Time` if `stop` is
+    /// set and at least `min` solutions.
+    pub fn generate_until_stopped(
+        board: &Board,
+        min: u32,
+        max: u32,
+        stop: &AtomicBool,
+        ids: SolutionIds,
+    ) -> (Result<(), ResourcesExceeded>, Self) {
+        Self::generate_until(board, min, max, None, Some(stop), ids)
+    }
+
+    fn generate_until(
+        board: &Board,
+        min: u32,
+        max: u32,
+        deadline: Option<Instant>,
+        stop: Option<&AtomicBool>,
+        mut ids: SolutionIds,
     ) -> (Result<(), ResourcesExceeded>, Self) {
         let original_squares = Small::all().collect();
         let mut table = Self::with_capacity(original_squares, max);
@@ -102,7 +148,7 @@ impl SolutionTable {
                     if num_solutions >= max {
                         return (Err(ResourcesExceeded::Memory), table);
                     }
-                    let id = rng.random_bits_64();
+                    let id = ids.id(&filled_board);
                     table.append(id, &filled_board.squares);
                     num_solutions += 1;
                 }
@@ -117,7 +163,15 @@ impl SolutionTable {
                 && num_solutions >= min
             {
                 since_last_time_check = 0;
-                if Instant::now() >= deadline {
+                let time_exceeded = match deadline {
+                    Some(deadline) => Instant::now() >= deadline,
+                    None => false,
+                };
+                let stopped = match stop {
+                    Some(stop) => stop.load(Ordering::Relaxed),
+                    None => false,
+                };
+                if time_exceeded || stopped {
                     return (Err(ResourcesExceeded::Time), table);
                 }
             }
@@ -136,6 +190,21 @@ impl SolutionTable {
         table
     }
 
+    /// The solutions mapped back by `symmetry`, with new IDs. All squares must be in the table,
+    /// as in freshly generated tables.
+    pub fn backward(&self, symmetry: &Symmetry, mut ids: SolutionIds) -> Self {
+        assert_eq!(self.num_squares(), 81);
+        let mut table = Self::with_capacity(self.original_squares.clone(), self.len);
+        for solution in self.iter() {
+            let filled_board = FilledBoard {
+                squares: solution.digits().try_into().unwrap(),
+            };
+            let filled_board = symmetry.backward_filled_board(&filled_board);
+            table.append(ids.id(&filled_board), &filled_board.squares);
+        }
+        table
+    }
+
     pub fn move_tables(&self) -> Vec<SquareMoveTable> {
         let mut move_tables = vec![SquareMoveTable::default(); usize::from(self.num_squares())];
 
@@ -222,6 +291,47 @@ impl SolutionTable {
     }
 }
 
+/// How solution IDs are assigned.
+pub enum SolutionIds<'a> {
+    Random(&'a mut RandomGenerator),
+    /// `content_id` with the given key.
+    ///
+    /// `SolutionTable::hash` is then a fingerprint of the set of solutions: it doesn't depend on
+    /// the order of generation, the process or the machine.
+    Content(u64),
+}
+
+impl SolutionIds<'_> {
+    fn id(&mut self, filled_board: &FilledBoard) -> u64 {
+        match self {
+            Self::Random(rng) => rng.random_bits_64(),
+            Self::Content(key) => content_id(*key, &filled_board.squares),
+        }
+    }
+}
+
+pub const DEFAULT_CONTENT_ID_KEY: u64 = 0x4e69_6e65_4469_6769;
+
+/// Solution ID derived from the digits of the solution by a keyed hash.
+pub fn content_id(key: u64, digits: &[Digit; 81]) -> u64 {
+    let mut hash = mix64(key);
+    for chunk in digits.chunks(16) {
+        let mut word: u64 = 0;
+        for &digit in chunk {
+            word = word << 4 | u64::from(u8::from(Small::<9>::from(digit)));
+        }
+        hash = mix64(hash ^ word);
+    }
+    hash
+}
+
+/// The splitmix64 finalizer.
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct SolutionRef<'a>(&'a [u8]);
 
@@ -0,0 +1,430 @@
+use crate::evaluation::EvaluationMidgame; // submission::skip
+use crate::mcts::MctsMidgame; // submission::skip
+use crate::proof_number::ProofNumberEndgame; // submission::skip
+use crate::{
+    board::{Board, FullMove, Move},
+    endgame::{EndgameResult, EndgameSolver},
+    error::{InvalidInput, ResourcesExceeded},
+    log,
+    midgame::{self, MidgameMove},
+    random::RandomGenerator,
+    settings::{self, Settings},
+    solution_table::{SolutionIds, SolutionTable, DEFAULT_CONTENT_ID_KEY},
+    symmetry::{normalize_board, Symmetry},
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// State shared by the strategies of a player.
+pub struct SearchContext {
+    pub settings: Settings,
+    pub endgame_solver: EndgameSolver,
+    pub rng: RandomGenerator,
+}
+
+impl SearchContext {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            endgame_solver: EndgameSolver::new(settings.transposition_table_memory),
+            rng: RandomGenerator::with_time_nonce(),
+            settings,
+        }
+    }
+
+    pub fn solution_ids(&mut self) -> SolutionIds<'_> {
+        if self.settings.content_solution_ids {
+            SolutionIds::Content(DEFAULT_CONTENT_ID_KEY)
+        } else {
+            SolutionIds::Random(&mut self.rng)
+        }
+    }
+
+    /// The board in the frame used for its solutions: normalized if `normalize_positions`.
+    pub fn normalize(&self, board: &Board) -> (Board, Symmetry) {
+        if self.settings.normalize_positions {
+            normalize_board(board)
+        } else {
+            (*board, Symmetry::identity())
+        }
+    }
+}
+
+/// Chooses moves before solutions are generated.
+pub trait OpeningStrategy: Send {
+    /// None ends the opening for this move. May fill in implied squares on `board`.
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        board: &mut Board,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> Option<Move>;
+}
+
+/// Chooses moves when only some solutions are known.
+pub trait MidgameStrategy: Send {
+    /// `solutions` has at least `SOLUTIONS_MIN` solutions. May fill in implied squares on
+    /// `board`.
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        board: &mut Board,
+        solutions: &SolutionTable,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> MidgameDecision;
+}
+
+pub struct MidgameDecision {
+    pub mov: FullMove,
+    /// All solutions after the move, if they were generated.
+    pub solutions: Option<SolutionTable>,
+}
+
+/// Chooses moves when all solutions are known.
+pub trait EndgameStrategy: Send {
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        solutions: &SolutionTable,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> FullMove;
+}
+
+/// A strategy for each phase.
+pub struct Strategies {
+    pub opening: Box<dyn OpeningStrategy>,
+    pub midgame: Box<dyn MidgameStrategy>,
+    pub endgame: Box<dyn EndgameStrategy>,
+}
+
+impl Strategies {
+    pub fn new() -> Self {
+        Self::from(StrategySpec::default())
+    }
+}
+
+impl From<StrategySpec> for Strategies {
+    fn from(spec: StrategySpec) -> Self {
+        Self {
+            opening: match spec.opening {
+                OpeningKind::Random => Box::new(RandomOpening),
+                OpeningKind::None => Box::new(NoOpening),
+            },
+            midgame: match spec.midgame {
+                MidgameKind::Defense => Box::new(DefensiveMidgame),
+                MidgameKind::Evaluation => Box::new(EvaluationMidgame), // submission::skip
+                MidgameKind::Mcts => Box::new(MctsMidgame),             // submission::skip
+            },
+            endgame: match spec.endgame {
+                EndgameKind::BestEffort => Box::new(BestEffortEndgame),
+                EndgameKind::ProofNumber => Box::new(ProofNumberEndgame::new()), // submission::skip
+            },
+        }
+    }
+}
+
+/// Which strategy to use in each phase, as in the `strategies` setting.
+///
+/// Parsed from `phase=name` pairs separated by `,` or `:`, e.g. `opening=none,endgame=proof-number`.
+/// Missing phases get the default strategy.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StrategySpec {
+    pub opening: OpeningKind,
+    pub midgame: MidgameKind,
+    pub endgame: EndgameKind,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OpeningKind {
+    #[default]
+    Random,
+    None,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MidgameKind {
+    #[default]
+    Defense,
+    Evaluation, // submission::skip
+    Mcts,       // submission::skip
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EndgameKind {
+    #[default]
+    BestEffort,
+    ProofNumber, // submission::skip
+}
+
+impl FromStr for StrategySpec {
+    type Err = InvalidInput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidInput> {
+        let mut spec = Self::default();
+        for item in s.split([',', ':']).filter(|item| !item.is_empty()) {
+            let (phase, name) = item.split_once('=').ok_or(InvalidInput)?;
+            match (phase, name) {
+                ("opening", "random") => spec.opening = OpeningKind::Random,
+                ("opening", "none") => spec.opening = OpeningKind::None,
+                ("midgame", "defense") => spec.midgame = MidgameKind::Defense,
+                ("midgame", "evaluation") => spec.midgame = MidgameKind::Evaluation, // submission::skip
+                ("midgame", "mcts") => spec.midgame = MidgameKind::Mcts, // submission::skip
+                ("endgame", "best-effort") => spec.endgame = EndgameKind::BestEffort,
+                ("endgame", "proof-number") => spec.endgame = EndgameKind::ProofNumber, // submission::skip
+                _ => return Err(InvalidInput),
+            }
+        }
+        Ok(spec)
+    }
+}
+
+impl Display for StrategySpec {
+    /// Separated by `:`, so that it fits in a comma-separated list of settings.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let opening = match self.opening {
+            OpeningKind::Random => "random",
+            OpeningKind::None => "none",
+        };
+        let midgame = match self.midgame {
+            MidgameKind::Defense => "defense",
+            MidgameKind::Evaluation => "evaluation", // submission::skip
+            MidgameKind::Mcts => "mcts",             // submission::skip
+        };
+        let endgame = match self.endgame {
+            EndgameKind::BestEffort => "best-effort",
+            EndgameKind::ProofNumber => "proof-number", // submission::skip
+        };
+        write!(f, "opening={opening}:midgame={midgame}:endgame={endgame}")
+    }
+}
+
+/// Random moves that keep at least 2 solutions, until `opening_max_squares` squares are filled.
+pub struct RandomOpening;
+
+impl OpeningStrategy for RandomOpening {
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        board: &mut Board,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> Option<Move> {
+        if 81 - board.empty_squares().size() > context.settings.opening_max_squares {
+            return None;
+        }
+        let movegen_deadline =
+            start_time + time_left.mul_f64(context.settings.opening_movegen_time_fraction);
+        let (res, solutions) =
+            SolutionTable::generate(board, 2, 2, movegen_deadline, &mut context.rng);
+        match res {
+            Ok(()) => {
+                log::write_line!(Info, "What? Opening all solutions generated.",);
+                return None;
+            }
+            Err(ResourcesExceeded::Memory) => {}
+            Err(e) => {
+                log::write_line!(Info, "opening sol gen error: {e}",);
+                return None;
+            }
+        }
+        let moves = midgame::generate_moves(board, &solutions, movegen_deadline);
+        assert!(!moves.is_empty());
+        log::write_line!(
+            Info,
+            "opening movegen time {:.3?}",
+            Instant::now().saturating_duration_since(start_time)
+        );
+        if 81 - board.empty_squares().size() <= context.settings.opening_max_squares {
+            Some(context.rng.choose(&moves).mov)
+        } else {
+            None
+        }
+    }
+}
+
+/// Goes straight to the midgame.
+pub struct NoOpening;
+
+impl OpeningStrategy for NoOpening {
+    fn choose_move(
+        &mut self,
+        _context: &mut SearchContext,
+        _board: &mut Board,
+        _start_time: Instant,
+        _time_left: Duration,
+    ) -> Option<Move> {
+        None
+    }
+}
+
+/// Tries the moves with the most solutions first, and plays the first one that the endgame
+/// solver can't refute.
+pub struct DefensiveMidgame;
+
+impl MidgameStrategy for DefensiveMidgame {
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        board: &mut Board,
+        solutions: &SolutionTable,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> MidgameDecision {
+        let (mut moves, start_time, time_left) =
+            generate_midgame_moves(context, board, solutions, start_time, time_left);
+        moves.sort_by_key(|x| x.num_solutions_lower_bound);
+        defend(context, board, &moves, start_time, time_left)
+    }
+}
+
+/// Returns the moves and the remaining time.
+pub(crate) fn generate_midgame_moves(
+    context: &mut SearchContext,
+    board: &mut Board,
+    solutions: &SolutionTable,
+    start_time: Instant,
+    time_left: Duration,
+) -> (Vec<MidgameMove>, Instant, Duration) {
+    assert!(solutions.len() >= settings::SOLUTIONS_MIN);
+    let movegen_deadline =
+        start_time + time_left.mul_f64(context.settings.midgame_movegen_time_fraction);
+    let moves = midgame::generate_moves(board, solutions, movegen_deadline);
+    assert!(!moves.is_empty());
+    let t = Instant::now();
+    let used_time = t.saturating_duration_since(start_time);
+    log::write_line!(Info, "midgame movegen time {used_time:.3?}",);
+    (moves, t, time_left.saturating_sub(used_time))
+}
+
+/// Tries the moves from last to first, and plays the first one that the endgame solver can't
+/// refute.
+pub(crate) fn defend(
+    context: &mut SearchContext,
+    board: &Board,
+    moves: &[MidgameMove],
+    mut start_time: Instant,
+    mut time_left: Duration,
+) -> MidgameDecision {
+    let num_moves = moves.len();
+    let mut best_losing_move_index = num_moves - 1;
+    let mut best_losing_move_difficulty = 0;
+    let defense_solutions_max = context.settings.midgame_defense_solutions_max;
+
+    for (defense_index, mov) in moves.iter().enumerate().rev() {
+        if mov.num_solutions_lower_bound <= defense_solutions_max {
+            let defense_deadline =
+                start_time + time_left.mul_f64(context.settings.midgame_defense_time_fraction);
+            let defense_deadline_extended = start_time
+                + time_left.mul_f64(context.settings.midgame_defense_extended_time_fraction);
+            let mut new_board = *board;
+            new_board.make_move(mov.mov).unwrap();
+            // Symmetric candidates share transposition table results.
+            let (new_board, symmetry) = context.normalize(&new_board);
+            let (solgen_result, solutions) = SolutionTable::generate_with_ids(
+                &new_board,
+                0,
+                defense_solutions_max,
+                defense_deadline,
+                context.solution_ids(),
+            );
+            if let Err(e) = solgen_result {
+                log::write_line!(
+                    Info,
+                    "midgame defense safe {defense_index} / {num_moves} num_solutions >= {num_solutions} {e}",
+                    num_solutions = solutions.len(),
+                );
+                return MidgameDecision {
+                    mov: FullMove::Move(mov.mov),
+                    solutions: None,
+                };
+            }
+            log::write_line!(
+                Info,
+                "midgame defense {defense_index} / {num_moves} num_solutions = {num_solutions}",
+                num_solutions = solutions.len()
+            );
+            match context.endgame_solver.solve(
+                &solutions,
+                Some(defense_deadline),
+                defense_deadline_extended,
+                context.settings.midgame_defense_difficulty_max,
+            ) {
+                Ok(EndgameResult::Loss) => {
+                    log::write_line!(Info, "midgame win!");
+                    let solutions = solutions.backward(&symmetry, context.solution_ids());
+                    let full_move = if solutions.len() == 1 {
+                        FullMove::MoveClaimUnique(mov.mov)
+                    } else {
+                        FullMove::Move(mov.mov)
+                    };
+                    return MidgameDecision {
+                        mov: full_move,
+                        solutions: Some(solutions),
+                    };
+                }
+                Ok(EndgameResult::Win(maybe_move)) => {
+                    log::write_line!(Info, "midgame PANIC");
+                    if let Some(mov) = maybe_move {
+                        if mov.num_solutions > best_losing_move_difficulty {
+                            best_losing_move_index = defense_index;
+                            best_losing_move_difficulty = mov.num_solutions;
+                        }
+                    }
+                    let t = Instant::now();
+                    time_left = time_left.saturating_sub(t.saturating_duration_since(start_time));
+                    start_time = t;
+                }
+                Err(e) => {
+                    log::write_line!(Info, "safe {e}");
+                    return MidgameDecision {
+                        mov: FullMove::Move(mov.mov),
+                        solutions: Some(solutions.backward(&symmetry, context.solution_ids())),
+                    };
+                }
+            }
+        } else {
+            log::write_line!(
+                Info,
+                "midgame num_solutions >= {num_solutions}",
+                num_solutions = mov.num_solutions_lower_bound,
+            );
+            return MidgameDecision {
+                mov: FullMove::Move(mov.mov),
+                solutions: None,
+            };
+        }
+    }
+    log::write_line!(
+        Info,
+        "midgame lost difficulty={best_losing_move_difficulty}"
+    );
+    MidgameDecision {
+        mov: FullMove::Move(moves[best_losing_move_index].mov),
+        solutions: None,
+    }
+}
+
+/// `EndgameSolver::choose_move_best_effort`.
+pub struct BestEffortEndgame;
+
+impl EndgameStrategy for BestEffortEndgame {
+    fn choose_move(
+        &mut self,
+        context: &mut SearchContext,
+        solutions: &SolutionTable,
+        start_time: Instant,
+        time_left: Duration,
+    ) -> FullMove {
+        context.endgame_solver.choose_move_best_effort(
+            &context.settings,
+            solutions,
+            start_time,
+            time_left,
+        )
+    }
+}
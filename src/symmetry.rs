@@ -1,5 +1,5 @@
 use crate::{
-    board::{box_major_coordinates, Board, Coordinates, Move},
+    board::{box_major_coordinates, Board, Coordinates, FilledBoard, FullMove, Move},
     digit::Digit,
     permutation::{Permutation, ALL_PERMUTATIONS_2, ALL_PERMUTATIONS_3},
     random::RandomGenerator,
@@ -93,6 +93,32 @@ impl Symmetry {
         }
     }
 
+    pub fn backward_full_move(&self, mov: FullMove) -> FullMove {
+        match mov {
+            FullMove::Move(mov) => FullMove::Move(self.backward_move(mov)),
+            FullMove::MoveClaimUnique(mov) => FullMove::MoveClaimUnique(self.backward_move(mov)),
+            FullMove::ClaimUnique => FullMove::ClaimUnique,
+        }
+    }
+
+    pub fn forward_filled_board(&self, board: &FilledBoard) -> FilledBoard {
+        let mut squares = board.squares;
+        for square in Small::<81>::all() {
+            let (coord, digit) = self.forward_coord_digit(square.into(), board.squares[square]);
+            squares[Small::<81>::from(coord)] = digit;
+        }
+        FilledBoard { squares }
+    }
+
+    pub fn backward_filled_board(&self, board: &FilledBoard) -> FilledBoard {
+        let mut squares = board.squares;
+        for square in Small::<81>::all() {
+            let (coord, digit) = self.backward_coord_digit(square.into(), board.squares[square]);
+            squares[Small::<81>::from(coord)] = digit;
+        }
+        FilledBoard { squares }
+    }
+
     pub fn forward_board(&self, board: &Board) -> Board {
         let mut new_board = Board::new();
         for coord in box_major_coordinates() {
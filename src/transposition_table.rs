@@ -1,5 +1,22 @@
-use crate::{endgame::EndgameResult, log};
-use std::mem;
+use crate::{
+    board::Move, digit::Digit, endgame::EndgameResult, log, small::Small,
+    solution_table::EndgameMoveNoHash,
+};
+use std::{
+    mem,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
+
+/// Storage for endgame results, used by `EndgameSolver`.
+pub trait EndgameTable {
+    fn new_era(&mut self);
+    fn find(&self, hash: u64) -> Option<EndgameResult>;
+    fn insert(&mut self, hash: u64, result: EndgameResult);
+    /// The result and its distance, if the distance is known.
+    fn find_distance(&self, hash: u64) -> Option<(EndgameResult, u8)>;
+    /// Stores a result with its distance. A win must have the fastest winning move.
+    fn insert_distance(&mut self, hash: u64, result: EndgameResult, distance: u8);
+}
 
 pub struct TranspositionTable {
     table: Vec<Bucket>,
@@ -9,12 +26,7 @@ pub struct TranspositionTable {
 
 impl TranspositionTable {
     pub fn new(memory: usize) -> Self {
-        let num_buckets = (memory / (2 * mem::size_of::<Bucket>()) + 1).next_power_of_two();
-        log::write_line!(
-            Info,
-            "transposition table {} MiB",
-            (num_buckets * mem::size_of::<Bucket>()) >> 20
-        );
+        let num_buckets = num_buckets(memory, mem::size_of::<Bucket>());
         Self {
             table: vec![Bucket::new(); num_buckets],
             era: 1,
@@ -24,48 +36,310 @@ impl TranspositionTable {
 
     pub fn new_era(&mut self) {
         self.era = self.era.wrapping_add(1);
+        // Era 0 marks empty entries.
+        if self.era == 0 {
+            self.era = 1;
+        }
     }
 
     pub fn find(&self, hash: u64) -> Option<EndgameResult> {
         // Safety: index_mask guarantees the index is in range.
         let bucket = unsafe { self.table.get_unchecked((hash as usize) & self.index_mask) };
-        for entry in &bucket.entries {
-            if entry.hash == hash {
-                return Some(entry.result);
-            }
-        }
-        None
+        bucket.find(hash).map(|entry| entry.result)
     }
 
     pub fn insert(&mut self, hash: u64, result: EndgameResult) {
+        self.insert_entry(hash, result, 0);
+    }
+
+    pub fn find_distance(&self, hash: u64) -> Option<(EndgameResult, u8)> {
+        // Safety: index_mask guarantees the index is in range.
+        let bucket = unsafe { self.table.get_unchecked((hash as usize) & self.index_mask) };
+        match bucket.find(hash) {
+            Some(entry) if entry.distance != 0 => Some((entry.result, entry.distance)),
+            _ => None,
+        }
+    }
+
+    pub fn insert_distance(&mut self, hash: u64, result: EndgameResult, distance: u8) {
+        assert_ne!(distance, 0);
+        self.insert_entry(hash, result, distance);
+    }
+
+    fn insert_entry(&mut self, hash: u64, result: EndgameResult, distance: u8) {
         // Safety: index_mask guarantees the index is in range.
         let bucket = unsafe {
             self.table
                 .get_unchecked_mut((hash as usize) & self.index_mask)
         };
-        let best_entry = bucket
+        bucket.insert(hash, result, distance, self.era);
+    }
+
+    /// All stored results.
+    pub fn entries(&self) -> impl Iterator<Item = (u64, EndgameResult)> + '_ {
+        self.table
+            .iter()
+            .flat_map(|bucket| bucket.entries.iter())
+            .filter(|entry| entry.era != 0)
+            .map(|entry| (entry.hash, entry.result))
+    }
+}
+
+impl EndgameTable for TranspositionTable {
+    fn new_era(&mut self) {
+        TranspositionTable::new_era(self)
+    }
+
+    fn find(&self, hash: u64) -> Option<EndgameResult> {
+        TranspositionTable::find(self, hash)
+    }
+
+    fn insert(&mut self, hash: u64, result: EndgameResult) {
+        TranspositionTable::insert(self, hash, result)
+    }
+
+    fn find_distance(&self, hash: u64) -> Option<(EndgameResult, u8)> {
+        TranspositionTable::find_distance(self, hash)
+    }
+
+    fn insert_distance(&mut self, hash: u64, result: EndgameResult, distance: u8) {
+        TranspositionTable::insert_distance(self, hash, result, distance)
+    }
+}
+
+/// A lock-free transposition table that can be shared between threads.
+///
+/// Each entry is stored as two words: `data` and `hash ^ data`. An entry torn by a concurrent
+/// write fails the check and is treated as missing.
+pub struct SharedTranspositionTable {
+    table: Vec<SharedBucket>,
+    index_mask: usize,
+    era: AtomicU8,
+}
+
+impl SharedTranspositionTable {
+    pub fn new(memory: usize) -> Self {
+        let num_buckets = num_buckets(memory, mem::size_of::<SharedBucket>());
+        Self {
+            table: (0..num_buckets).map(|_| SharedBucket::new()).collect(),
+            era: AtomicU8::new(1),
+            index_mask: num_buckets - 1,
+        }
+    }
+
+    pub fn new_era(&self) {
+        self.era.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn find(&self, hash: u64) -> Option<EndgameResult> {
+        self.find_entry(hash).map(|entry| entry.result)
+    }
+
+    pub fn insert(&self, hash: u64, result: EndgameResult) {
+        self.insert_entry(hash, result, 0);
+    }
+
+    pub fn find_distance(&self, hash: u64) -> Option<(EndgameResult, u8)> {
+        match self.find_entry(hash) {
+            Some(entry) if entry.distance != 0 => Some((entry.result, entry.distance)),
+            _ => None,
+        }
+    }
+
+    pub fn insert_distance(&self, hash: u64, result: EndgameResult, distance: u8) {
+        assert_ne!(distance, 0);
+        self.insert_entry(hash, result, distance);
+    }
+
+    fn find_entry(&self, hash: u64) -> Option<Entry> {
+        // Safety: index_mask guarantees the index is in range.
+        let bucket = unsafe { self.table.get_unchecked((hash as usize) & self.index_mask) };
+        for entry in &bucket.entries {
+            let (entry_hash, data) = entry.load();
+            if entry_hash == hash {
+                if let Some(entry) = unpack_entry(hash, data) {
+                    return Some(entry);
+                }
+            }
+        }
+        None
+    }
+
+    fn insert_entry(&self, hash: u64, result: EndgameResult, distance: u8) {
+        let era = self.era.load(Ordering::Relaxed);
+        // Safety: index_mask guarantees the index is in range.
+        let bucket = unsafe { self.table.get_unchecked((hash as usize) & self.index_mask) };
+
+        // Same replacement policy as `Bucket::insert`.
+        let (best_entry, best_hash, best_data) = bucket
             .entries
-            .iter_mut()
-            .min_by_key(|entry| (entry.hash != hash, entry.era == self.era))
+            .iter()
+            .map(|entry| {
+                let (entry_hash, data) = entry.load();
+                (entry, entry_hash, unpack_entry(entry_hash, data))
+            })
+            .min_by_key(|&(_, entry_hash, old)| {
+                (
+                    entry_hash != hash,
+                    matches!(old, Some(old) if old.era == era),
+                )
+            })
             .unwrap();
-        if best_entry.hash == hash {
-            best_entry.era = self.era;
-            // Don't overwrite with less complete result.
-            if !matches!(result, EndgameResult::Win(None)) {
-                best_entry.result = result;
+        let mut new = Entry {
+            hash,
+            era,
+            distance,
+            result,
+        };
+        if best_hash == hash {
+            if let Some(old) = best_data {
+                new.merge_old(&old);
             }
-        } else {
-            best_entry.hash = hash;
-            best_entry.result = result;
-            best_entry.era = self.era;
         }
+        best_entry.store(hash, pack_entry(&new));
+    }
+
+    /// All stored results.
+    pub fn entries(&self) -> impl Iterator<Item = (u64, EndgameResult)> + '_ {
+        self.table
+            .iter()
+            .flat_map(|bucket| bucket.entries.iter())
+            .filter_map(|entry| {
+                let (hash, data) = entry.load();
+                let entry = unpack_entry(hash, data)?;
+                Some((hash, entry.result))
+            })
     }
 }
 
+impl EndgameTable for &SharedTranspositionTable {
+    fn new_era(&mut self) {
+        SharedTranspositionTable::new_era(self)
+    }
+
+    fn find(&self, hash: u64) -> Option<EndgameResult> {
+        SharedTranspositionTable::find(self, hash)
+    }
+
+    fn insert(&mut self, hash: u64, result: EndgameResult) {
+        SharedTranspositionTable::insert(self, hash, result)
+    }
+
+    fn find_distance(&self, hash: u64) -> Option<(EndgameResult, u8)> {
+        SharedTranspositionTable::find_distance(self, hash)
+    }
+
+    fn insert_distance(&mut self, hash: u64, result: EndgameResult, distance: u8) {
+        SharedTranspositionTable::insert_distance(self, hash, result, distance)
+    }
+}
+
+fn num_buckets(memory: usize, bucket_size: usize) -> usize {
+    let num_buckets = (memory / (2 * bucket_size) + 1).next_power_of_two();
+    log::write_line!(
+        Info,
+        "transposition table {} MiB",
+        (num_buckets * bucket_size) >> 20
+    );
+    num_buckets
+}
+
+struct SharedEntry {
+    hash_xor_data: AtomicU64,
+    data: AtomicU64,
+}
+
+impl SharedEntry {
+    fn load(&self) -> (u64, u64) {
+        let data = self.data.load(Ordering::Relaxed);
+        let hash_xor_data = self.hash_xor_data.load(Ordering::Relaxed);
+        (hash_xor_data ^ data, data)
+    }
+
+    fn store(&self, hash: u64, data: u64) {
+        self.data.store(data, Ordering::Relaxed);
+        self.hash_xor_data.store(hash ^ data, Ordering::Relaxed);
+    }
+}
+
+#[repr(align(64))]
+struct SharedBucket {
+    entries: [SharedEntry; 4],
+}
+
+impl SharedBucket {
+    fn new() -> Self {
+        Self {
+            entries: [(); 4].map(|_| SharedEntry {
+                hash_xor_data: AtomicU64::new(0),
+                data: AtomicU64::new(0),
+            }),
+        }
+    }
+}
+
+// Packed entry data: `pack_result` in bits 0..45, era in bits 48..56, distance in bits 56..64.
+fn pack_entry(entry: &Entry) -> u64 {
+    u64::from(entry.distance) << 56 | u64::from(entry.era) << 48 | pack_result(entry.result)
+}
+
+fn unpack_entry(hash: u64, data: u64) -> Option<Entry> {
+    let result = unpack_result(data & ((1 << 45) - 1))?;
+    Some(Entry {
+        hash,
+        era: (data >> 48) as u8,
+        distance: (data >> 56) as u8,
+        result,
+    })
+}
+
+// Packed result:
+// bits 0..32: num_solutions
+// bits 32..39: square
+// bits 39..43: digit
+// bits 43..45: 0 = empty, 1 = Loss, 2 = Win(None), 3 = Win(Some)
+pub(crate) fn pack_result(result: EndgameResult) -> u64 {
+    match result {
+        EndgameResult::Loss => 1 << 43,
+        EndgameResult::Win(None) => 2 << 43,
+        EndgameResult::Win(Some(mov)) => {
+            3 << 43
+                | u64::from(u8::from(Small::<9>::from(mov.mov.digit))) << 39
+                | u64::from(u8::from(mov.mov.square)) << 32
+                | u64::from(mov.num_solutions)
+        }
+    }
+}
+
+pub(crate) fn unpack_result(data: u64) -> Option<EndgameResult> {
+    if data >> 45 != 0 {
+        return None;
+    }
+    let result = match data >> 43 {
+        0 => return None,
+        1 => EndgameResult::Loss,
+        2 => EndgameResult::Win(None),
+        _ => {
+            let square = Small::<81>::try_from(((data >> 32) & 0x7f) as u8).ok()?;
+            let digit = Small::<9>::try_from(((data >> 39) & 0xf) as u8).ok()?;
+            EndgameResult::Win(Some(EndgameMoveNoHash {
+                mov: Move {
+                    square,
+                    digit: Digit::from(digit),
+                },
+                num_solutions: data as u32,
+            }))
+        }
+    };
+    Some(result)
+}
+
 #[derive(Copy, Clone, Debug)]
 struct Entry {
     hash: u64,
     era: u8,
+    /// Plies until the solution is unique, 0 if unknown.
+    distance: u8,
     result: EndgameResult,
 }
 
@@ -74,9 +348,20 @@ impl Entry {
         Self {
             hash: 0,
             era: 0,
+            distance: 0,
             result: EndgameResult::Loss,
         }
     }
+
+    /// Keeps the old result for the same position if it is more complete.
+    fn merge_old(&mut self, old: &Entry) {
+        if self.distance == 0
+            && (old.distance != 0 || matches!(self.result, EndgameResult::Win(None)))
+        {
+            self.distance = old.distance;
+            self.result = old.result;
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -91,4 +376,26 @@ impl Bucket {
             entries: [Entry::new(); 4],
         }
     }
+
+    fn find(&self, hash: u64) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.hash == hash)
+    }
+
+    fn insert(&mut self, hash: u64, result: EndgameResult, distance: u8, era: u8) {
+        let best_entry = self
+            .entries
+            .iter_mut()
+            .min_by_key(|entry| (entry.hash != hash, entry.era == era))
+            .unwrap();
+        let mut new = Entry {
+            hash,
+            era,
+            distance,
+            result,
+        };
+        if best_entry.hash == hash {
+            new.merge_old(best_entry);
+        }
+        *best_entry = new;
+    }
 }
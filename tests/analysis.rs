@@ -0,0 +1,114 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use sudoku_game::{
+    analysis::{run_analysis, AnalysisCommand, SearchLimits},
+    settings::Settings,
+    strategy::Strategies,
+};
+
+#[test]
+fn test_parse_analysis_command() {
+    match "position startpos moves Aa1 Bd2".parse().unwrap() {
+        AnalysisCommand::Position { moves, .. } => assert_eq!(moves.len(), 2),
+        _ => panic!(),
+    }
+    match "go movetime 500 nodes 1000".parse().unwrap() {
+        AnalysisCommand::Go(limits) => assert_eq!(
+            limits,
+            SearchLimits {
+                movetime: Some(Duration::from_millis(500)),
+                nodes: Some(1000),
+            }
+        ),
+        _ => panic!(),
+    }
+    match "setoption name solutions_max value 5000".parse().unwrap() {
+        AnalysisCommand::SetOption { name, value } => {
+            assert_eq!(name, "solutions_max");
+            assert_eq!(value, "5000");
+        }
+        _ => panic!(),
+    }
+    assert!("go infinite".parse::<AnalysisCommand>().is_ok());
+    assert!("stop".parse::<AnalysisCommand>().is_ok());
+    assert!("position".parse::<AnalysisCommand>().is_err());
+    assert!("position startpos moves Zz1"
+        .parse::<AnalysisCommand>()
+        .is_err());
+    assert!("go movetime".parse::<AnalysisCommand>().is_err());
+    assert!("stop now".parse::<AnalysisCommand>().is_err());
+    assert!("unknown".parse::<AnalysisCommand>().is_err());
+}
+
+#[test]
+fn test_run_analysis() {
+    let input = "isready\n\
+        setoption name transposition_table_memory value 1048576\n\
+        position 509032070003050024000000010620000000000000803000080000070600000004000000800340000\n\
+        go\n";
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let dyn_output: Arc<Mutex<dyn Write + Send>> = output.clone();
+    run_analysis(
+        Settings::new(),
+        Strategies::new(),
+        input.as_bytes(),
+        dyn_output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "readyok");
+    assert_eq!(lines[1], "info solutions 360 all");
+    assert!(lines[lines.len() - 2].starts_with("info verdict win "));
+    assert_eq!(lines[lines.len() - 1], "bestmove Ed1!");
+}
+
+#[test]
+fn test_run_analysis_midgame() {
+    let input = "setoption name transposition_table_memory value 1048576\n\
+        position startpos moves Aa1 Ed5\n\
+        go movetime 1000\n";
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let dyn_output: Arc<Mutex<dyn Write + Send>> = output.clone();
+    run_analysis(
+        Settings::new(),
+        Strategies::new(),
+        input.as_bytes(),
+        dyn_output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[0].starts_with("info solutions ") && lines[0].ends_with(" partial"));
+    assert!(lines[1].starts_with("info move "));
+    assert!(lines[lines.len() - 2].starts_with("info verdict unknown "));
+    assert!(lines[lines.len() - 1].starts_with("bestmove "));
+}
+
+#[test]
+fn test_run_analysis_midgame_nodes() {
+    let input = "setoption name transposition_table_memory value 1048576\n\
+        setoption name solutions_max value 100\n\
+        position 509032070003050024000000010620000000000000803000080000070600000004000000800340000\n\
+        go nodes 1000\n";
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let dyn_output: Arc<Mutex<dyn Write + Send>> = output.clone();
+    run_analysis(
+        Settings::new(),
+        Strategies::new(),
+        input.as_bytes(),
+        dyn_output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    let verdict: Vec<&str> = lines[lines.len() - 2].split(' ').collect();
+    assert_eq!(verdict[..2], ["info", "verdict"]);
+    let nodes: u64 = verdict[4].parse().unwrap();
+    // The node that exceeds the limit is counted.
+    assert!(nodes > 0 && nodes <= 1001);
+    assert!(lines[lines.len() - 1].starts_with("bestmove "));
+}
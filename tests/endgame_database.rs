@@ -0,0 +1,67 @@
+use std::{
+    env, fs,
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::Board,
+    digit::Digit,
+    endgame::{EndgameResult, EndgameSolver},
+    endgame_database::EndgameDatabase,
+    small::Small,
+    solution_table::{SolutionIds, SolutionTable, DEFAULT_CONTENT_ID_KEY},
+    transposition_table::TranspositionTable,
+};
+
+#[test]
+fn test_endgame_database_save_load() {
+    let board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(3600);
+    let (res, solutions) = SolutionTable::generate_with_ids(
+        &board,
+        0,
+        10_000,
+        deadline,
+        SolutionIds::Content(DEFAULT_CONTENT_ID_KEY),
+    );
+    res.unwrap();
+    let mut solver = EndgameSolver::new(1 << 20);
+    let result = solver.solve(&solutions, None, deadline, None).unwrap();
+
+    let mut database = EndgameDatabase::new();
+    database.insert(&solutions, result);
+    assert_eq!(database.find(&solutions), Some(result));
+
+    let path = env::temp_dir().join(format!("endgame_database_test_{}", std::process::id()));
+    database.save(&path).unwrap();
+    let loaded = EndgameDatabase::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.len(), database.len());
+    assert_eq!(loaded.find(&solutions), Some(result));
+
+    let mut table = TranspositionTable::new(1 << 20);
+    assert!(loaded.preload(&solutions, &mut table));
+    assert_eq!(table.find(solutions.hash()), Some(result));
+}
+
+#[test]
+fn test_endgame_database_hash_collision() {
+    let mut solutions = SolutionTable::with_capacity(vec![Small::new(0), Small::new(1)], 2);
+    solutions.append(0b0110, &['1', '2'].map(|c| Digit::try_from(c).unwrap()));
+    solutions.append(0b0101, &['2', '1'].map(|c| Digit::try_from(c).unwrap()));
+    // Same hash, different solutions.
+    let mut colliding = SolutionTable::with_capacity(vec![Small::new(0), Small::new(1)], 2);
+    colliding.append(0b1010, &['1', '3'].map(|c| Digit::try_from(c).unwrap()));
+    colliding.append(0b1001, &['3', '1'].map(|c| Digit::try_from(c).unwrap()));
+    assert_eq!(solutions.hash(), colliding.hash());
+
+    let mut database = EndgameDatabase::new();
+    database.insert(&solutions, EndgameResult::Win(None));
+    assert_eq!(database.find(&solutions), Some(EndgameResult::Win(None)));
+    assert_eq!(database.find(&colliding), None);
+    let mut table = TranspositionTable::new(1 << 20);
+    assert!(!database.preload(&colliding, &mut table));
+    assert_eq!(table.find(colliding.hash()), None);
+}
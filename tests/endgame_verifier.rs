@@ -0,0 +1,110 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::{Board, Move},
+    digit::Digit,
+    endgame::{EndgameResult, EndgameSolver},
+    endgame_verifier::{EndgameVerifier, VerificationError},
+    proof_tree::ProofTree,
+    random::RandomGenerator,
+    small::Small,
+    solution_table::{EndgameMoveNoHash, SolutionTable},
+};
+
+#[test]
+fn test_verify_endgame_solver() {
+    let mut rng = RandomGenerator::with_nonce(0);
+    let mut solver = EndgameSolver::new(1 << 20);
+    let input = fs::read_to_string("data/in1000x100").unwrap();
+    for line in input.lines().take(20) {
+        let board: Board = line.parse().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let (res, solutions) = SolutionTable::generate(&board, 0, 10_000, deadline, &mut rng);
+        res.unwrap();
+        let result = solver.solve(&solutions, None, deadline, None).unwrap();
+        let tree = solver.proof_tree(&solutions, deadline).unwrap();
+
+        let mut verifier = EndgameVerifier::new(&solutions);
+        assert_eq!(verifier.is_win(), matches!(result, EndgameResult::Win(_)));
+        assert_eq!(verifier.verify_result(result), Ok(()));
+        assert_eq!(verifier.verify_proof_tree(&tree), Ok(()));
+    }
+}
+
+#[test]
+fn test_endgame_distance() {
+    let mut rng = RandomGenerator::with_nonce(1);
+    let mut solver = EndgameSolver::new(1 << 20);
+    let input = fs::read_to_string("data/in1000x100").unwrap();
+    for line in input.lines().take(10) {
+        let board: Board = line.parse().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let (res, solutions) = SolutionTable::generate(&board, 0, 10_000, deadline, &mut rng);
+        res.unwrap();
+        let value = solver.solve_distance(&solutions, deadline).unwrap();
+        let result = solver.solve(&solutions, None, deadline, None).unwrap();
+
+        let mut verifier = EndgameVerifier::new(&solutions);
+        assert_eq!(value.distance, verifier.distance());
+        assert_eq!(value.is_win(), matches!(result, EndgameResult::Win(_)));
+        if value.is_win() {
+            let win = EndgameResult::Win(value.best_move);
+            assert_eq!(verifier.verify_result(win), Ok(()));
+        }
+    }
+}
+
+#[test]
+fn test_verify_wrong_claims() {
+    // Solutions differ in squares 0 and 1: 12, 21, 13.
+    let mut solutions = SolutionTable::with_capacity(vec![Small::new(0), Small::new(1)], 3);
+    for (id, digits) in [(1, ['1', '2']), (2, ['2', '1']), (3, ['1', '3'])] {
+        solutions.append(id, &digits.map(|c| Digit::try_from(c).unwrap()));
+    }
+    let mut verifier = EndgameVerifier::new(&solutions);
+    let mov = |s: &str| -> Move { s.parse().unwrap() };
+
+    assert!(verifier.is_win());
+    assert_eq!(
+        verifier.verify_result(EndgameResult::Loss),
+        Err(VerificationError::WrongResult)
+    );
+    assert_eq!(
+        verifier.verify_result(EndgameResult::Win(Some(EndgameMoveNoHash {
+            mov: mov("Ab2"),
+            num_solutions: 1,
+        }))),
+        Ok(())
+    );
+    assert_eq!(
+        verifier.verify_result(EndgameResult::Win(Some(EndgameMoveNoHash {
+            mov: mov("Aa1"),
+            num_solutions: 2,
+        }))),
+        Err(VerificationError::NotWinning(mov("Aa1")))
+    );
+    assert_eq!(
+        verifier.verify_result(EndgameResult::Win(Some(EndgameMoveNoHash {
+            mov: mov("Ac1"),
+            num_solutions: 1,
+        }))),
+        Err(VerificationError::IllegalMove(mov("Ac1")))
+    );
+
+    let tree: ProofTree = "win\nAb2 1\n".parse().unwrap();
+    assert_eq!(verifier.verify_proof_tree(&tree), Ok(()));
+    let tree: ProofTree = "win\nAb2 2\n".parse().unwrap();
+    assert_eq!(
+        verifier.verify_proof_tree(&tree),
+        Err(VerificationError::WrongNumSolutions(mov("Ab2")))
+    );
+    let tree: ProofTree = "win\nAa1 2\n".parse().unwrap();
+    assert!(matches!(
+        verifier.verify_proof_tree(&tree),
+        Err(VerificationError::MissingRefutation(_))
+    ));
+    let tree: ProofTree = "loss\n".parse().unwrap();
+    assert!(verifier.verify_proof_tree(&tree).is_err());
+}
@@ -0,0 +1,34 @@
+use sudoku_game::{
+    board::Board,
+    evaluation::{features, EvaluationModel},
+};
+
+#[test]
+fn test_features() {
+    let f = features(&Board::new(), 0.0, 0.0);
+    assert_eq!(f, [1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0]);
+
+    let board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let f = features(&board, 359.0, 999.0);
+    assert_eq!(f[1], 60.0 / 81.0);
+    assert_eq!(f[2], 0.0);
+    assert!(f[3] > 0.0 && f[3] < 1.0);
+    assert!((f[5] - 360f64.ln() / 49.55).abs() < 1e-9);
+    assert!((f[6] - 1000f64.ln() / 49.55).abs() < 1e-9);
+}
+
+#[test]
+fn test_evaluation_model() {
+    let model: EvaluationModel = "0.5:-1:0:0:0:2:0".parse().unwrap();
+    assert_eq!(model.to_string(), "0.5:-1:0:0:0:2:0");
+    assert_eq!(model.win_probability(&[0.0; 7]), 0.5);
+    assert!((model.win_probability(&[1.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0]) - 0.5).abs() < 1e-9);
+    assert!("1:2:3:4:5:6".parse::<EvaluationModel>().is_err());
+    assert!("0:0:0:0:0:0:0:0".parse::<EvaluationModel>().is_err());
+
+    let p = EvaluationModel::new().evaluate(&Board::new(), 1e9, 1e12);
+    assert!(p > 0.0 && p < 1.0);
+}
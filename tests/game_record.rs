@@ -0,0 +1,78 @@
+use std::time::Duration;
+use sudoku_game::game_record::{GameRecord, GameResult, RecordedMove, Verdict};
+
+const RECORD: &str = "\
+event: test game
+player0: sudoku-game
+player1: sudoku-game strategies=endgame=proof-number
+time_control: 10000
+result: 1 failure
+Aa1 time=120
+Bd2 time=95 # opening: random
+Ie7! time=2100 verdict=loss
+";
+
+#[test]
+fn test_parse_game_record() {
+    let record: GameRecord = RECORD.parse().unwrap();
+    assert_eq!(record.event.as_deref(), Some("test game"));
+    assert_eq!(
+        record.players[1],
+        "sudoku-game strategies=endgame=proof-number"
+    );
+    assert_eq!(record.time_control, Some(Duration::from_secs(10)));
+    assert_eq!(
+        record.result,
+        Some(GameResult {
+            winner: 1,
+            failure: true
+        })
+    );
+    assert_eq!(record.moves.len(), 3);
+    assert_eq!(
+        record.moves[1],
+        RecordedMove {
+            mov: "Bd2".parse().unwrap(),
+            time: Some(Duration::from_millis(95)),
+            verdict: None,
+            comment: Some("opening: random".to_string()),
+        }
+    );
+    assert_eq!(record.moves[2].mov, "Ie7!".parse().unwrap());
+    assert_eq!(record.moves[2].verdict, Some(Verdict::Loss));
+    assert_eq!(record.to_string(), RECORD);
+}
+
+#[test]
+fn test_parse_all_game_records() {
+    let mut record = GameRecord::new(["a".to_string(), "b".to_string()]);
+    record.moves.push(RecordedMove::new("Aa1".parse().unwrap()));
+    record.result = Some(GameResult {
+        winner: 0,
+        failure: true,
+    });
+    record.termination = Some("timeout".to_string());
+    let file = format!("# Two games.\n{record}\n\n{RECORD}\n");
+    let records = GameRecord::parse_all(&file).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0], record);
+    assert_eq!(records[1], RECORD.parse().unwrap());
+    assert!(file.parse::<GameRecord>().is_err());
+}
+
+#[test]
+fn test_parse_game_record_errors() {
+    assert!("player0: a\nAa1\n".parse::<GameRecord>().is_err());
+    assert!("player0: a\nplayer1: b\nresult: 2\n"
+        .parse::<GameRecord>()
+        .is_err());
+    assert!("player0: a\nplayer1: b\nAa1 time=x\n"
+        .parse::<GameRecord>()
+        .is_err());
+    assert!("player0: a\nplayer1: b\nAa1\nevent: late\n"
+        .parse::<GameRecord>()
+        .is_err());
+    assert!("player0: a\nplayer1: b\ncolor: red\n"
+        .parse::<GameRecord>()
+        .is_err());
+}
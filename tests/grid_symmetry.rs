@@ -0,0 +1,53 @@
+use sudoku_game::{
+    board::FilledBoard,
+    grid_symmetry::{are_equivalent, automorphisms, canonical_grid},
+    random::RandomGenerator,
+    symmetry::Symmetry,
+};
+
+const GRID: &str =
+    "549132678183756924762894315628973541457261893931485762275618439394527186816349257";
+/// The grid with the most automorphisms.
+const PATTERN: &str =
+    "123456789456789123789123456231564897564897231897231564312645978645978312978312645";
+
+#[test]
+fn test_canonical_grid() {
+    let mut rng = RandomGenerator::with_nonce(0);
+    let grid: FilledBoard = GRID.parse().unwrap();
+    let (canonical, symmetry) = canonical_grid(&grid);
+    assert_eq!(symmetry.forward_filled_board(&grid), canonical);
+    assert!(canonical.to_string().starts_with("123456789"));
+    for _ in 0..3 {
+        let other = Symmetry::random(&mut rng).forward_filled_board(&grid);
+        assert_eq!(canonical_grid(&other).0, canonical);
+    }
+    let pattern: FilledBoard = PATTERN.parse().unwrap();
+    assert_ne!(canonical_grid(&pattern).0, canonical);
+}
+
+#[test]
+fn test_are_equivalent() {
+    let mut rng = RandomGenerator::with_nonce(1);
+    let grid: FilledBoard = GRID.parse().unwrap();
+    for _ in 0..3 {
+        let other = Symmetry::random(&mut rng).forward_filled_board(&grid);
+        let symmetry = are_equivalent(&grid, &other).unwrap();
+        assert_eq!(symmetry.forward_filled_board(&grid), other);
+    }
+    let pattern: FilledBoard = PATTERN.parse().unwrap();
+    assert!(are_equivalent(&grid, &pattern).is_none());
+}
+
+#[test]
+fn test_automorphisms() {
+    let grid: FilledBoard = GRID.parse().unwrap();
+    assert_eq!(automorphisms(&grid).len(), 1);
+
+    let pattern: FilledBoard = PATTERN.parse().unwrap();
+    let symmetries = automorphisms(&pattern);
+    assert_eq!(symmetries.len(), 648);
+    for symmetry in &symmetries {
+        assert_eq!(symmetry.forward_filled_board(&pattern), pattern);
+    }
+}
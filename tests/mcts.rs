@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+use sudoku_game::{
+    board::{Board, FullMove},
+    endgame::{EndgameResult, EndgameSolver},
+    mcts, midgame,
+    random::RandomGenerator,
+    settings::Settings,
+    solution_table::SolutionTable,
+    strategy::SearchContext,
+};
+
+fn context() -> SearchContext {
+    let mut settings = Settings::new();
+    settings.transposition_table_memory = 1 << 20;
+    SearchContext::new(settings)
+}
+
+fn search(context: &mut SearchContext, board: &mut Board, time: Duration) -> FullMove {
+    let start_time = Instant::now();
+    let mut rng = RandomGenerator::with_nonce(1);
+    let (_, solutions) = SolutionTable::generate(board, 2, 1000, start_time + time, &mut rng);
+    let moves = midgame::generate_moves(board, &solutions, start_time + time);
+    mcts::search(context, board, moves, start_time + 2 * time).mov
+}
+
+#[test]
+fn test_search_claims_win() {
+    // Two solutions: any move leaves a unique one.
+    let mut board: Board =
+        "509132670103756920762894315628973541457261893931485762275618439394527186816349257"
+            .parse()
+            .unwrap();
+    let mut context = context();
+    let mov = search(&mut context, &mut board, Duration::from_secs(1));
+    assert!(matches!(mov, FullMove::MoveClaimUnique(_)));
+}
+
+#[test]
+fn test_search_midgame() {
+    let mut board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let mut context = context();
+    let mov = search(&mut context, &mut board, Duration::from_millis(300));
+    let FullMove::Move(mov) = mov else {
+        panic!("unexpected move {mov}");
+    };
+    assert!(board.make_move(mov).is_ok());
+}
+
+#[test]
+fn test_search_prefers_refuted_replies() {
+    // After Fe8, both replies searched lose, but not all replies are searched.
+    let mut board: Board =
+        "549030000000000900760000010608070041050060800000005060070618430004520000006040050"
+            .parse()
+            .unwrap();
+    let mut context = context();
+    context.settings.mcts_leaf_solutions = 100;
+    context.settings.mcts_moves_max = 2;
+    context.rng = RandomGenerator::with_nonce(1);
+    context.settings.mcts_iterations_max = Some(200);
+    let mov = search(&mut context, &mut board, Duration::from_secs(3600));
+    assert_eq!(mov.to_string(), "Fe8");
+}
+
+#[test]
+fn test_search_tries_more_root_moves() {
+    // The 8 moves with the most solutions lose, winning moves have fewer.
+    let mut board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let mut context = context();
+    context.settings.mcts_moves_max = 2;
+    context.settings.mcts_iterations_max = Some(1000);
+    let mut rng = RandomGenerator::with_nonce(1);
+    let deadline = Instant::now() + Duration::from_secs(3600);
+    let (_, solutions) = SolutionTable::generate(&board, 2, 1000, deadline, &mut rng);
+    let moves = midgame::generate_moves(&mut board, &solutions, deadline);
+    let decision = mcts::search(&mut context, &board, moves, deadline);
+    let solutions = decision.solutions.unwrap();
+    let mut solver = EndgameSolver::new(1 << 20);
+    assert_eq!(
+        solver.solve(&solutions, None, deadline, None),
+        Ok(EndgameResult::Loss)
+    );
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+use sudoku_game::{
+    board::{Board, FullMove, Move},
+    opening_book::{BookOpening, OpeningBook},
+    random::RandomGenerator,
+    referee::Referee,
+    settings::Settings,
+    strategy::{OpeningStrategy, SearchContext},
+    symmetry::{normalize_board, Symmetry},
+};
+
+#[test]
+fn test_book_symmetry() {
+    let mut rng = RandomGenerator::with_nonce(0);
+    let board: Board =
+        "...........................................5.....3...................43.........."
+            .parse()
+            .unwrap();
+    let mov: Move = "Aa1".parse().unwrap();
+    let mut book = OpeningBook::new();
+    book.add_game_move(&board, mov, true);
+    book.add_game_move(&board, mov, false);
+    book.add_game_move(&board, mov, true);
+    assert_eq!(book.len(), 1);
+    let position = book.position(&normalize_board(&board).0).unwrap();
+    assert_eq!(position.moves.len(), 1);
+    assert_eq!((position.moves[0].wins, position.moves[0].losses), (2, 1));
+
+    for _ in 0..10 {
+        let symmetry = Symmetry::random(&mut rng);
+        let book_move = book.choose_move(&symmetry.forward_board(&board)).unwrap();
+        let mut expected = symmetry.forward_board(&board);
+        expected.make_move(symmetry.forward_move(mov)).unwrap();
+        let mut actual = symmetry.forward_board(&board);
+        actual.make_move(book_move).unwrap();
+        assert_eq!(normalize_board(&actual).0, normalize_board(&expected).0);
+    }
+    assert_eq!(book.choose_move(&Board::new()), None);
+}
+
+#[test]
+fn test_book_verdicts() {
+    let board = Board::new();
+    let good: Move = "Aa1".parse().unwrap();
+    let bad: Move = "Ee5".parse().unwrap();
+    let mut book = OpeningBook::new();
+    book.add_game_move(&board, good, false);
+    book.add_game_move(&board, bad, true);
+    assert_eq!(book.choose_move(&board), Some(bad));
+
+    let mut after_bad = board;
+    after_bad.make_move(bad).unwrap();
+    book.set_win(&after_bad, true);
+    assert_eq!(book.choose_move(&board), None);
+
+    let mut after_good = board;
+    after_good.make_move(good).unwrap();
+    book.set_win(&after_good, false);
+    let chosen = book.choose_move(&board).unwrap();
+    let mut after_chosen = board;
+    after_chosen.make_move(chosen).unwrap();
+    assert_eq!(
+        normalize_board(&after_chosen).0,
+        normalize_board(&after_good).0
+    );
+}
+
+#[test]
+fn test_book_parse() {
+    let mut book = OpeningBook::new();
+    let board: Board =
+        "...........................................5.....3...................43.........."
+            .parse()
+            .unwrap();
+    book.add_game_move(&board, "Aa1".parse().unwrap(), true);
+    book.add_game_move(&Board::new(), "Ee5".parse().unwrap(), false);
+    book.set_win(&board, true);
+    let text = book.to_string();
+    assert_eq!(text.parse::<OpeningBook>().unwrap(), book);
+
+    assert!("# Comment.\nposition: .................................................................................\nAa1 wins=1 losses=2\n"
+        .parse::<OpeningBook>()
+        .is_ok());
+    assert!("Aa1 wins=1\n".parse::<OpeningBook>().is_err());
+    assert!("position: ....\n".parse::<OpeningBook>().is_err());
+    assert!(
+        "position: .................................................................................\nverdict: draw\n"
+            .parse::<OpeningBook>()
+            .is_err()
+    );
+}
+
+#[test]
+fn test_book_opening_checks_moves() {
+    let board: Board =
+        "12345678........................................................................."
+            .parse()
+            .unwrap();
+    // Implied: 9 is the only digit left in the row.
+    let implied: Move = "Ai9".parse().unwrap();
+    let mut book = OpeningBook::new();
+    book.add_game_move(&board, implied, true);
+    let mut strategy = BookOpening::new(book);
+    let mut settings = Settings::new();
+    settings.transposition_table_memory = 1 << 20;
+    let mut context = SearchContext::new(settings);
+
+    let mut new_board = board;
+    let mov = strategy
+        .choose_move(
+            &mut context,
+            &mut new_board,
+            Instant::now(),
+            Duration::from_secs(10),
+        )
+        .unwrap();
+    assert_ne!(mov, implied);
+    let mut referee = Referee::with_board(board);
+    assert!(referee.make_move(FullMove::Move(mov)).is_ok());
+
+    context.settings.opening_max_squares = 7;
+    let mut new_board = board;
+    let mov = strategy.choose_move(
+        &mut context,
+        &mut new_board,
+        Instant::now(),
+        Duration::from_secs(10),
+    );
+    assert_eq!(mov, None);
+}
@@ -0,0 +1,92 @@
+use sudoku_game::{
+    board::Board,
+    opening_suite::{parse_suite, Opening, OpeningError, SuiteError},
+    referee::IllegalMove,
+};
+
+#[test]
+fn test_parse_opening() {
+    let opening: Opening = "Aa1 Ed5,Ii9".parse().unwrap();
+    assert_eq!(opening.moves.len(), 3);
+    assert_eq!(opening.to_string(), "Aa1 Ed5 Ii9");
+    assert_eq!(opening.to_argument(), "Aa1,Ed5,Ii9");
+    assert_eq!(opening.referee().unwrap().turn(), 1);
+
+    let opening: Opening =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    assert_eq!(opening.moves.len(), 21);
+    assert!(opening.referee().is_ok());
+
+    let opening: Opening =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000 Ed1,Fe2"
+            .parse()
+            .unwrap();
+    assert_eq!(opening.moves.len(), 23);
+    assert_eq!(opening.moves[22], "Fe2".parse().unwrap());
+
+    assert!("Aa1 Zz9".parse::<Opening>().is_err());
+    // Same square twice.
+    assert_eq!(
+        "Aa1 Aa2".parse::<Opening>().unwrap().referee().err(),
+        Some(OpeningError::IllegalMove(
+            "Aa2".parse().unwrap(),
+            IllegalMove::Occupied
+        ))
+    );
+}
+
+#[test]
+fn test_opening_from_board() {
+    // Column a has 1 to 7, so Hd9 implies Ia9: Ia9 must be played before Hd9.
+    let board: Board =
+        "100000000200000000300000000400000000500000000600000000700000000000900000900000000"
+            .parse()
+            .unwrap();
+    let opening = Opening::try_from(&board).unwrap();
+    assert_eq!(opening.to_string(), "Aa1 Ba2 Ca3 Da4 Ea5 Fa6 Ga7 Ia9 Hd9");
+    assert_eq!(*opening.referee().unwrap().board(), board);
+    let row_major: Opening = "Aa1 Ba2 Ca3 Da4 Ea5 Fa6 Ga7 Hd9 Ia9".parse().unwrap();
+    assert_eq!(
+        row_major.referee().err(),
+        Some(OpeningError::IllegalMove(
+            "Ia9".parse().unwrap(),
+            IllegalMove::Implied
+        ))
+    );
+
+    // Any square of row A is implied by the others.
+    let board: Board =
+        "123456789000000000000000000000000000000000000000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+    assert_eq!(
+        Opening::try_from(&board),
+        Err(OpeningError::IllegalMove(
+            "Ai9".parse().unwrap(),
+            IllegalMove::Implied
+        ))
+    );
+}
+
+#[test]
+fn test_parse_suite() {
+    let suite = parse_suite("# Suite.\nAa1 Ed5\n\nIi9\n").unwrap();
+    assert_eq!(suite.len(), 2);
+    assert_eq!(suite[1].to_string(), "Ii9");
+    assert_eq!(
+        parse_suite("Aa1\n# Comment.\nAa1 Ab1\n"),
+        Err(SuiteError {
+            line: 3,
+            error: OpeningError::IllegalMove("Ab1".parse().unwrap(), IllegalMove::Unsolvable),
+        })
+    );
+    assert_eq!(
+        parse_suite("Aa1 Zz9\n"),
+        Err(SuiteError {
+            line: 1,
+            error: OpeningError::InvalidInput,
+        })
+    );
+}
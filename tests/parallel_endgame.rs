@@ -0,0 +1,45 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::Board,
+    endgame::{EndgameResult, EndgameSolver},
+    parallel_endgame::ParallelEndgameSolver,
+    random::RandomGenerator,
+    solution_table::SolutionTable,
+};
+
+#[test]
+fn test_parallel_endgame_same_as_serial() {
+    let input = fs::read_to_string("data/in1000x1000").unwrap();
+    let mut rng = RandomGenerator::with_nonce(0);
+    for line in input.lines().take(10) {
+        let board: Board = line.parse().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let (res, solutions) = SolutionTable::generate(&board, 0, 10_000, deadline, &mut rng);
+        res.unwrap();
+        // Fresh solvers, so that table contents from earlier boards cannot change the results.
+        let mut serial_solver = EndgameSolver::new(1 << 20);
+        let mut parallel_solver = ParallelEndgameSolver::new(1 << 20, 4);
+        let serial_result = serial_solver.solve(&solutions, None, deadline, None);
+        let parallel_result = parallel_solver.solve(&solutions, None, deadline, None);
+        match parallel_result.unwrap() {
+            EndgameResult::Win(Some(mov)) => {
+                assert!(matches!(serial_result.unwrap(), EndgameResult::Win(_)));
+                // The move may be different, but it has to win.
+                let mut new_board = board;
+                new_board.make_move(mov.mov).unwrap();
+                let (res, new_solutions) =
+                    SolutionTable::generate(&new_board, 0, 10_000, deadline, &mut rng);
+                res.unwrap();
+                assert_eq!(new_solutions.len(), mov.num_solutions);
+                let after_move = EndgameSolver::new(1 << 20)
+                    .solve(&new_solutions, None, deadline, None)
+                    .unwrap();
+                assert_eq!(after_move, EndgameResult::Loss);
+            }
+            result => assert_eq!(result, serial_result.unwrap()),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::Board,
+    endgame::{EndgameResult, EndgameSolver},
+    proof_number::ProofNumberSolver,
+    random::RandomGenerator,
+    solution_table::SolutionTable,
+};
+
+#[test]
+fn test_proof_number_same_as_endgame_solver() {
+    let input = fs::read_to_string("data/in1000x1000").unwrap();
+    let mut rng = RandomGenerator::with_nonce(0);
+    let mut endgame_solver = EndgameSolver::new(1 << 20);
+    let mut proof_number_solver = ProofNumberSolver::new(1 << 20, 300);
+    let mut leaf_solver = EndgameSolver::new(1 << 20);
+    for line in input.lines().take(20) {
+        let board: Board = line.parse().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let (res, solutions) = SolutionTable::generate(&board, 0, 10_000, deadline, &mut rng);
+        res.unwrap();
+        let expected = endgame_solver
+            .solve(&solutions, None, deadline, None)
+            .unwrap();
+        let result = proof_number_solver
+            .solve(&mut leaf_solver, &solutions, deadline)
+            .unwrap();
+        match result {
+            EndgameResult::Win(Some(mov)) => {
+                assert!(matches!(expected, EndgameResult::Win(_)));
+                // The move may be different, but it has to win.
+                let mut new_board = board;
+                new_board.make_move(mov.mov).unwrap();
+                let (res, new_solutions) =
+                    SolutionTable::generate(&new_board, 0, 10_000, deadline, &mut rng);
+                res.unwrap();
+                assert_eq!(new_solutions.len(), mov.num_solutions);
+                let after_move = endgame_solver
+                    .solve(&new_solutions, None, deadline, None)
+                    .unwrap();
+                assert_eq!(after_move, EndgameResult::Loss);
+            }
+            _ => assert_eq!(result, expected),
+        }
+    }
+}
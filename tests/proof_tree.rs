@@ -0,0 +1,54 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::Board,
+    endgame::{EndgameResult, EndgameSolver},
+    proof_tree::ProofTree,
+    random::RandomGenerator,
+    solution_table::SolutionTable,
+};
+
+#[test]
+fn test_proof_tree() {
+    let input = fs::read_to_string("data/in1000x100").unwrap();
+    let mut rng = RandomGenerator::with_nonce(0);
+    let mut solver = EndgameSolver::new(1 << 20);
+    for line in input.lines().take(5) {
+        let board: Board = line.parse().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let (res, solutions) = SolutionTable::generate(&board, 0, 10_000, deadline, &mut rng);
+        res.unwrap();
+        let result = solver.solve(&solutions, None, deadline, None).unwrap();
+        let is_win = matches!(result, EndgameResult::Win(_));
+
+        let variation = solver.principal_variation(&solutions, deadline).unwrap();
+        assert_eq!(variation.len() % 2 == 1, is_win);
+        assert_eq!(variation.last().unwrap().num_solutions, 1);
+        let mut board = board;
+        for mov in &variation {
+            board.make_move(mov.mov).unwrap();
+        }
+
+        let tree = solver.proof_tree(&solutions, deadline).unwrap();
+        assert_eq!(tree.is_win(), is_win);
+        let text = tree.to_string();
+        assert_eq!(text.parse::<ProofTree>(), Ok(tree));
+    }
+}
+
+#[test]
+fn test_proof_tree_format() {
+    let text = "win\nAa1 3\n  Bb2 2\n    Cc4 1\n  Bb3 2\n    Cc5 1\n";
+    let tree: ProofTree = text.parse().unwrap();
+    assert!(tree.is_win());
+    assert_eq!(tree.num_moves(), 5);
+    assert_eq!(tree.to_string(), text);
+
+    assert!("loss\n".parse::<ProofTree>().is_ok());
+    assert!("win\n".parse::<ProofTree>().is_err());
+    assert!("win\nAa1 3\n   Bb2 2\n".parse::<ProofTree>().is_err());
+    assert!("win\nAa1 3\n  Bb2 2\n".parse::<ProofTree>().is_err());
+    assert!("loss\n  Aa1 3\n".parse::<ProofTree>().is_err());
+}
@@ -0,0 +1,79 @@
+use sudoku_game::{
+    board::{Board, FullMove},
+    game_record::GameResult,
+    referee::{IllegalMove, Referee},
+};
+
+const BOARD: &str =
+    "509032070003050024000000010620000000000000803000080000070600000004000000800340000";
+
+fn referee() -> Referee {
+    Referee::with_board(BOARD.parse::<Board>().unwrap())
+}
+
+fn try_move(mov: &str) -> Result<(), IllegalMove> {
+    referee().make_move(mov.parse().unwrap())
+}
+
+#[test]
+fn test_illegal_moves() {
+    assert_eq!(try_move("Aa5"), Err(IllegalMove::Occupied));
+    assert_eq!(try_move("Ab5"), Err(IllegalMove::Unsolvable));
+    assert_eq!(try_move("Ag6"), Err(IllegalMove::Implied));
+    assert_eq!(try_move("Ab1!"), Err(IllegalMove::FalseClaim));
+    assert_eq!(try_move("!"), Err(IllegalMove::FalseClaim));
+    assert_eq!(try_move("Ab1"), Ok(()));
+
+    let mut referee = referee();
+    assert_eq!(referee.turn(), 0);
+    referee.make_move("Ab1".parse().unwrap()).unwrap();
+    assert_eq!(referee.turn(), 1);
+    assert_eq!(
+        referee.make_move("Ab2".parse().unwrap()),
+        Err(IllegalMove::Occupied)
+    );
+    assert_eq!(
+        referee.result(),
+        Some(GameResult {
+            winner: 0,
+            failure: true
+        })
+    );
+    assert_eq!(referee.moves().len(), 1);
+}
+
+#[test]
+fn test_correct_claim() {
+    let board: Board =
+        "549132678183756924762894315628973541457261893931485762275618439394527186816349250"
+            .parse()
+            .unwrap();
+    assert_eq!(
+        Referee::with_board(board).make_move("Ii7!".parse().unwrap()),
+        Err(IllegalMove::Implied)
+    );
+    let mut referee = Referee::with_board(board);
+    referee.make_move(FullMove::ClaimUnique).unwrap();
+    assert_eq!(
+        referee.result(),
+        Some(GameResult {
+            winner: 0,
+            failure: false
+        })
+    );
+}
+
+#[test]
+fn test_forfeit() {
+    let mut referee = Referee::new();
+    referee.make_move("Ab1".parse().unwrap()).unwrap();
+    assert_eq!(referee.result(), None);
+    referee.forfeit();
+    assert_eq!(
+        referee.result(),
+        Some(GameResult {
+            winner: 0,
+            failure: true
+        })
+    );
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+use sudoku_game::{
+    error::InvalidInput,
+    settings::Settings,
+    strategy::{EndgameKind, MidgameKind, OpeningKind},
+};
+
+#[test]
+fn test_set() {
+    let mut settings = Settings::new();
+    settings.set("solutions_max", "50000").unwrap();
+    settings.set("game_time_limit", "5000").unwrap();
+    settings
+        .set("endgame_offense_difficulty_max", "none")
+        .unwrap();
+    settings
+        .set("midgame_defense_difficulty_max", "100")
+        .unwrap();
+    assert_eq!(settings.solutions_max, 50000);
+    assert_eq!(settings.game_time_limit, Duration::from_secs(5));
+    assert_eq!(settings.endgame_offense_difficulty_max, None);
+    assert_eq!(settings.midgame_defense_difficulty_max, Some(100));
+
+    assert_eq!(settings.set("unknown", "1"), Err(InvalidInput));
+    assert_eq!(settings.set("solutions_max", "-1"), Err(InvalidInput));
+    assert_eq!(settings.set("endgame_distance", "yes"), Err(InvalidInput));
+}
+
+#[test]
+fn test_set_all() {
+    let mut settings = Settings::new();
+    settings
+        .set_all("endgame_distance=true,opening_max_squares=10")
+        .unwrap();
+    assert!(settings.endgame_distance);
+    assert_eq!(settings.opening_max_squares, 10);
+    assert_eq!(settings.set_all("endgame_distance"), Err(InvalidInput));
+
+    settings
+        .set_all("strategies=opening=none:endgame=proof-number")
+        .unwrap();
+    assert_eq!(settings.strategies.opening, OpeningKind::None);
+    assert_eq!(settings.strategies.midgame, MidgameKind::Defense);
+    assert_eq!(settings.strategies.endgame, EndgameKind::ProofNumber);
+}
+
+#[test]
+fn test_load_str() {
+    let mut settings = Settings::new();
+    settings
+        .load_str("# Comment.\n\nsolutions_max = 1000 # Fewer.\nendgame_distance = true\n")
+        .unwrap();
+    assert_eq!(settings.solutions_max, 1000);
+    assert!(settings.endgame_distance);
+    assert_eq!(settings.load_str("solutions_max"), Err(InvalidInput));
+}
+
+#[test]
+fn test_display_round_trip() {
+    let mut settings = Settings::new();
+    settings.set_all("strategies=endgame=proof-number,game_time_limit=1234,endgame_defense_difficulty_max=none,endgame_offense_time_fraction=0.125").unwrap();
+    let mut loaded = Settings::new();
+    loaded.load_str(&settings.to_string()).unwrap();
+    assert_eq!(loaded, settings);
+}
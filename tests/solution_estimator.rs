@@ -0,0 +1,25 @@
+use sudoku_game::{board::Board, random::RandomGenerator, solution_estimator::estimate_solutions};
+
+#[test]
+fn test_estimate_solutions() {
+    let mut rng = RandomGenerator::with_nonce(1);
+
+    let board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let estimate = estimate_solutions(&board, 10_000, &mut rng);
+    assert!((estimate.estimate / 360.0 - 1.0).abs() < 0.1);
+    assert!(estimate.lower() < estimate.estimate && estimate.upper() > estimate.estimate);
+
+    // 6670903752021072936960 solutions.
+    let estimate = estimate_solutions(&Board::new(), 10_000, &mut rng);
+    assert!((estimate.estimate / 6.670903752021073e21 - 1.0).abs() < 0.1);
+    assert!(estimate.std_error < 0.05 * estimate.estimate);
+
+    let board: Board =
+        "550000000000000000000000000000000000000000000000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+    assert_eq!(estimate_solutions(&board, 10, &mut rng).estimate, 0.0);
+}
@@ -1,4 +1,14 @@
-use sudoku_game::{board::Move, digit::Digit, small::Small, solution_table::SolutionTable};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::{Board, Move},
+    digit::Digit,
+    error::ResourcesExceeded,
+    small::Small,
+    solution_table::{content_id, SolutionIds, SolutionTable, DEFAULT_CONTENT_ID_KEY},
+};
 
 fn example_solution_table() -> SolutionTable {
     let original_squares = vec![Small::new(10), Small::new(20), Small::new(30)];
@@ -135,3 +145,65 @@ fn test_compress_and_gen_moves() {
     assert_eq!(orig_move.square, Small::new(20));
     assert_eq!(orig_move.digit, Digit::try_from('3').unwrap());
 }
+
+#[test]
+fn test_content_ids() {
+    let board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let mov: Move = "Ab1".parse().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(3600);
+    let generate = |board: &Board, key: u64| {
+        let (res, solutions) =
+            SolutionTable::generate_with_ids(board, 0, 10_000, deadline, SolutionIds::Content(key));
+        res.unwrap();
+        solutions
+    };
+
+    let solutions = generate(&board, DEFAULT_CONTENT_ID_KEY);
+    assert_eq!(
+        generate(&board, DEFAULT_CONTENT_ID_KEY).hash(),
+        solutions.hash()
+    );
+    assert_ne!(generate(&board, 1).hash(), solutions.hash());
+
+    let mut expected_hash = 0;
+    for solution in solutions.iter() {
+        let digits = solution.digits().try_into().unwrap();
+        assert_eq!(solution.id(), content_id(DEFAULT_CONTENT_ID_KEY, digits));
+        expected_hash ^= solution.id();
+    }
+    assert_eq!(solutions.hash(), expected_hash);
+
+    // The same position reached differently has the same hash.
+    let mut new_board = board;
+    new_board.make_move(mov).unwrap();
+    let new_solutions = generate(&new_board, DEFAULT_CONTENT_ID_KEY);
+    assert!(!new_solutions.is_empty());
+    assert_eq!(
+        solutions.filter(new_solutions.len(), mov).hash(),
+        new_solutions.hash()
+    );
+}
+
+#[test]
+fn test_generate_until_stopped() {
+    let board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(3600);
+    let ids = || SolutionIds::Content(DEFAULT_CONTENT_ID_KEY);
+    let (res, expected) = SolutionTable::generate_with_ids(&board, 0, 10_000, deadline, ids());
+    res.unwrap();
+
+    let stop = AtomicBool::new(false);
+    let (res, solutions) = SolutionTable::generate_until_stopped(&board, 0, 10_000, &stop, ids());
+    res.unwrap();
+    assert_eq!(solutions.hash(), expected.hash());
+
+    stop.store(true, Ordering::Relaxed);
+    let (res, _) = SolutionTable::generate_until_stopped(&Board::new(), 0, 10_000, &stop, ids());
+    assert_eq!(res, Err(ResourcesExceeded::Time));
+}
@@ -0,0 +1,33 @@
+use sudoku_game::strategy::{EndgameKind, MidgameKind, OpeningKind, StrategySpec};
+
+#[test]
+fn test_parse_strategies() {
+    assert_eq!("".parse::<StrategySpec>(), Ok(StrategySpec::default()));
+    assert!("opening=none,midgame=defense,endgame=best-effort"
+        .parse::<StrategySpec>()
+        .is_ok());
+    assert!("endgame=proof-number".parse::<StrategySpec>().is_ok());
+    assert!("midgame=evaluation".parse::<StrategySpec>().is_ok());
+    assert!("midgame=mcts".parse::<StrategySpec>().is_ok());
+    assert_eq!(
+        "midgame=mcts:endgame=proof-number".parse::<StrategySpec>(),
+        Ok(StrategySpec {
+            opening: OpeningKind::Random,
+            midgame: MidgameKind::Mcts,
+            endgame: EndgameKind::ProofNumber,
+        })
+    );
+    assert!("endgame".parse::<StrategySpec>().is_err());
+    assert!("endgame=unknown".parse::<StrategySpec>().is_err());
+    assert!("middlegame=defense".parse::<StrategySpec>().is_err());
+}
+
+#[test]
+fn test_strategies_round_trip() {
+    let spec: StrategySpec = "opening=none,endgame=proof-number".parse().unwrap();
+    assert_eq!(
+        spec.to_string(),
+        "opening=none:midgame=defense:endgame=proof-number"
+    );
+    assert_eq!(spec.to_string().parse(), Ok(spec));
+}
@@ -1,7 +1,9 @@
+use std::time::{Duration, Instant};
 use sudoku_game::{
-    board::{Board, Move},
+    board::{Board, FullMove, Move},
     random::RandomGenerator,
     small::Small,
+    solution_table::{SolutionIds, SolutionTable, DEFAULT_CONTENT_ID_KEY},
     symmetry::{normalize_board, Symmetry},
 };
 
@@ -56,3 +58,68 @@ fn test_normalize_board() {
     assert_eq!(symmetry.forward_move(mov1), mov2);
     assert_eq!(symmetry.backward_move(mov2), mov1);
 }
+
+#[test]
+fn test_normalized_solution_hash() {
+    let mut rng = RandomGenerator::with_nonce(1);
+    let board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let hash = |board: &Board| {
+        let (normalized, _) = normalize_board(board);
+        let (res, solutions) = SolutionTable::generate_with_ids(
+            &normalized,
+            0,
+            1000,
+            Instant::now() + Duration::from_secs(60),
+            SolutionIds::Content(DEFAULT_CONTENT_ID_KEY),
+        );
+        res.unwrap();
+        solutions.hash()
+    };
+    let expected = hash(&board);
+    for _ in 0..5 {
+        let symmetry = Symmetry::random(&mut rng);
+        assert_eq!(hash(&symmetry.forward_board(&board)), expected);
+    }
+}
+
+#[test]
+fn test_backward_full_move() {
+    let mut rng = RandomGenerator::with_nonce(2);
+    let symmetry = Symmetry::random(&mut rng);
+    let mov: Move = "Cf7".parse().unwrap();
+    let forward = symmetry.forward_move(mov);
+    assert_eq!(
+        symmetry.backward_full_move(FullMove::MoveClaimUnique(forward)),
+        FullMove::MoveClaimUnique(mov)
+    );
+    assert_eq!(
+        symmetry.backward_full_move(FullMove::ClaimUnique),
+        FullMove::ClaimUnique
+    );
+}
+
+#[test]
+fn test_solution_table_backward() {
+    let board: Board =
+        "509032070003050024000000010620000000000000803000080000070600000004000000800340000"
+            .parse()
+            .unwrap();
+    let generate = |board: &Board| {
+        let (res, solutions) = SolutionTable::generate_with_ids(
+            board,
+            0,
+            1000,
+            Instant::now() + Duration::from_secs(60),
+            SolutionIds::Content(DEFAULT_CONTENT_ID_KEY),
+        );
+        res.unwrap();
+        solutions
+    };
+    let (normalized, symmetry) = normalize_board(&board);
+    let solutions =
+        generate(&normalized).backward(&symmetry, SolutionIds::Content(DEFAULT_CONTENT_ID_KEY));
+    assert_eq!(solutions.hash(), generate(&board).hash());
+}
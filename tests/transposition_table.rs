@@ -1,4 +1,9 @@
-use sudoku_game::{endgame::EndgameResult, transposition_table::TranspositionTable};
+use std::thread;
+use sudoku_game::{
+    endgame::EndgameResult,
+    solution_table::EndgameMoveNoHash,
+    transposition_table::{SharedTranspositionTable, TranspositionTable},
+};
 
 #[test]
 fn test_transposition_table() {
@@ -16,3 +21,83 @@ fn test_transposition_table() {
     table.insert(0x50000000abcd, EndgameResult::Loss);
     assert_eq!(table.find(0xabcd), None);
 }
+
+#[test]
+fn test_shared_transposition_table() {
+    let table = SharedTranspositionTable::new(1 << 20);
+    let win = EndgameResult::Win(Some(EndgameMoveNoHash {
+        mov: "Ie9".parse().unwrap(),
+        num_solutions: 123456,
+    }));
+    table.insert(0xabcd, EndgameResult::Win(None));
+    table.insert(0x1234, EndgameResult::Loss);
+    table.insert(0x4321, win);
+    assert_eq!(table.find(0xabcd), Some(EndgameResult::Win(None)));
+    assert_eq!(table.find(0x1234), Some(EndgameResult::Loss));
+    assert_eq!(table.find(0x4321), Some(win));
+    assert_eq!(table.find(0x5678), None);
+
+    table.insert(0x4321, EndgameResult::Win(None));
+    assert_eq!(table.find(0x4321), Some(win));
+
+    table.insert(0x10000000abcd, EndgameResult::Loss);
+    table.insert(0x20000000abcd, EndgameResult::Loss);
+    table.insert(0x30000000abcd, EndgameResult::Loss);
+    assert_eq!(table.find(0xabcd), Some(EndgameResult::Win(None)));
+    table.insert(0x50000000abcd, EndgameResult::Loss);
+    assert_eq!(table.find(0xabcd), None);
+}
+
+#[test]
+fn test_shared_transposition_table_concurrent() {
+    fn result_for(hash: u64) -> EndgameResult {
+        EndgameResult::Win(Some(EndgameMoveNoHash {
+            mov: "Aa1".parse().unwrap(),
+            num_solutions: hash as u32,
+        }))
+    }
+
+    // Small table so that threads keep overwriting each other's entries.
+    let table = SharedTranspositionTable::new(1 << 10);
+    thread::scope(|scope| {
+        for thread_index in 0..4u64 {
+            let table = &table;
+            scope.spawn(move || {
+                for i in 0..100000u64 {
+                    let hash = i << 2 | thread_index;
+                    table.insert(hash, result_for(hash));
+                    let other_hash = hash ^ 1;
+                    if let Some(result) = table.find(other_hash) {
+                        assert_eq!(result, result_for(other_hash));
+                    }
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn test_transposition_table_distance() {
+    let win = EndgameResult::Win(Some(EndgameMoveNoHash {
+        mov: "Ie9".parse().unwrap(),
+        num_solutions: 123456,
+    }));
+    let mut table = TranspositionTable::new(1 << 20);
+    table.insert(0xabcd, EndgameResult::Loss);
+    assert_eq!(table.find_distance(0xabcd), None);
+    table.insert_distance(0xabcd, EndgameResult::Loss, 4);
+    table.insert(0xabcd, EndgameResult::Loss);
+    assert_eq!(table.find_distance(0xabcd), Some((EndgameResult::Loss, 4)));
+    table.insert_distance(0x1234, win, 5);
+    table.insert(0x1234, EndgameResult::Win(None));
+    assert_eq!(table.find(0x1234), Some(win));
+    assert_eq!(table.find_distance(0x1234), Some((win, 5)));
+
+    let table = SharedTranspositionTable::new(1 << 20);
+    table.insert_distance(0x1234, win, 77);
+    table.insert(0x1234, EndgameResult::Win(None));
+    assert_eq!(table.find(0x1234), Some(win));
+    assert_eq!(table.find_distance(0x1234), Some((win, 77)));
+    table.insert(0x4321, win);
+    assert_eq!(table.find_distance(0x4321), None);
+}
@@ -10,7 +10,10 @@ use std::{
 use sudoku_game::{
     board::Board,
     endgame::{EndgameResult, EndgameSolver},
+    parallel_endgame::ParallelEndgameSolver,
+    proof_number::ProofNumberSolver,
     random::RandomGenerator,
+    settings::Settings,
     solution_table::SolutionTable,
 };
 
@@ -21,14 +24,52 @@ struct Args {
 
     #[arg(short, long, default_value_t = 512)]
     ttable_mb: usize,
+
+    #[arg(short, long, default_value_t = 1)]
+    threads: usize,
+
+    /// Use proof-number search instead.
+    #[arg(short, long)]
+    proof_number: bool,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-    run_benchmark(&args.input, args.ttable_mb << 20)?;
+    let solver = if args.proof_number {
+        Solver::ProofNumber(
+            ProofNumberSolver::new(
+                args.ttable_mb << 20,
+                Settings::new().proof_number_leaf_solutions,
+            ),
+            EndgameSolver::new(args.ttable_mb << 20),
+        )
+    } else {
+        Solver::Parallel(ParallelEndgameSolver::new(
+            args.ttable_mb << 20,
+            args.threads,
+        ))
+    };
+    run_benchmark(&args.input, solver)?;
     Ok(())
 }
 
+enum Solver {
+    Parallel(ParallelEndgameSolver),
+    ProofNumber(ProofNumberSolver, EndgameSolver),
+}
+
+impl Solver {
+    fn solve(&mut self, solutions: &SolutionTable, deadline: Instant) -> EndgameResult {
+        match self {
+            Self::Parallel(solver) => solver.solve(solutions, None, deadline, None),
+            Self::ProofNumber(solver, leaf_solver) => {
+                solver.solve(leaf_solver, solutions, deadline)
+            }
+        }
+        .unwrap()
+    }
+}
+
 struct EndgameStatistics {
     num_puzzles: u32,
     total_solutions: u64,
@@ -57,12 +98,11 @@ impl Display for EndgameStatistics {
     }
 }
 
-fn run_benchmark(input_file_name: &Path, ttable_memory: usize) -> Result<(), Box<dyn Error>> {
+fn run_benchmark(input_file_name: &Path, mut endgame_solver: Solver) -> Result<(), Box<dyn Error>> {
     let input_file = File::open(input_file_name)?;
     let buf_reader = BufReader::new(input_file);
 
     let mut rng = RandomGenerator::with_nonce(0);
-    let mut endgame_solver = EndgameSolver::new(ttable_memory);
 
     let mut statistics_generate = EndgameStatistics::new();
     let mut statistics_win = EndgameStatistics::new();
@@ -85,24 +125,17 @@ fn run_benchmark(input_file_name: &Path, ttable_memory: usize) -> Result<(), Box
         let generated_time = Instant::now();
 
         statistics_generate.num_puzzles += 1;
-        statistics_generate.total_solutions += u64::try_from(solutions.len()).unwrap();
+        statistics_generate.total_solutions += u64::from(solutions.len());
         statistics_generate.total_time += generated_time.saturating_duration_since(start_time);
 
-        let result = endgame_solver
-            .solve(
-                &solutions,
-                None,
-                start_time + Duration::from_secs(24 * 3600),
-                None,
-            )
-            .unwrap();
+        let result = endgame_solver.solve(&solutions, start_time + Duration::from_secs(24 * 3600));
         let endgame_duration = Instant::now().saturating_duration_since(generated_time);
         let statistics = match result {
             EndgameResult::Win { .. } => &mut statistics_win,
             EndgameResult::Loss => &mut statistics_lose,
         };
         statistics.num_puzzles += 1;
-        statistics.total_solutions += u64::try_from(solutions.len()).unwrap();
+        statistics.total_solutions += u64::from(solutions.len());
         statistics.total_time += endgame_duration;
     }
 
@@ -0,0 +1,90 @@
+use clap::Parser;
+use std::{
+    error::Error,
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::{Board, FullMove},
+    endgame::EndgameSolver,
+    endgame_verifier::EndgameVerifier,
+    solution_table::{SolutionIds, SolutionTable, DEFAULT_CONTENT_ID_KEY},
+};
+
+#[derive(Debug, Parser)]
+struct Args {
+    board: Board,
+
+    #[arg(short, long, default_value_t = 512)]
+    ttable_mb: usize,
+
+    #[arg(short, long, default_value_t = 1_000_000)]
+    max_solutions: u32,
+
+    /// Write the full proof tree to this file.
+    #[arg(short, long)]
+    proof_tree: Option<PathBuf>,
+
+    /// Also find the fastest win or the longest resistance.
+    #[arg(short, long)]
+    distance: bool,
+
+    /// Check the result (and the proof tree) with the brute-force verifier.
+    #[arg(short, long)]
+    verify: bool,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let deadline = Instant::now() + Duration::from_secs(24 * 3600);
+    let (res, solutions) = SolutionTable::generate_with_ids(
+        &args.board,
+        0,
+        args.max_solutions,
+        deadline,
+        SolutionIds::Content(DEFAULT_CONTENT_ID_KEY),
+    );
+    res?;
+    println!("solutions: {}", solutions.len());
+
+    let mut endgame_solver = EndgameSolver::new(args.ttable_mb << 20);
+    let result = endgame_solver.solve(&solutions, None, deadline, None)?;
+    println!("result: {result:?}");
+
+    if args.distance {
+        let value = endgame_solver.solve_distance(&solutions, deadline)?;
+        println!("distance: {} plies", value.distance);
+    }
+
+    let variation = endgame_solver.principal_variation(&solutions, deadline)?;
+    let variation: Vec<String> = variation
+        .iter()
+        .map(|mov| {
+            let full_move = if mov.num_solutions == 1 {
+                FullMove::MoveClaimUnique(mov.mov)
+            } else {
+                FullMove::Move(mov.mov)
+            };
+            format!("{full_move} ({})", mov.num_solutions)
+        })
+        .collect();
+    println!("variation: {}", variation.join(" "));
+
+    let mut verifier = args.verify.then(|| EndgameVerifier::new(&solutions));
+    if let Some(verifier) = &mut verifier {
+        verifier.verify_result(result)?;
+        println!("result verified");
+    }
+
+    if let Some(path) = args.proof_tree {
+        let tree = endgame_solver.proof_tree(&solutions, deadline)?;
+        println!("proof tree: {} moves", tree.num_moves());
+        if let Some(verifier) = &verifier {
+            verifier.verify_proof_tree(&tree)?;
+            println!("proof tree verified");
+        }
+        fs::write(path, tree.to_string())?;
+    }
+    Ok(())
+}
@@ -1,47 +1,100 @@
 use clap::Parser;
 use std::{
-    path::PathBuf,
+    error::Error,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 use sudoku_game::{
-    board::{Board, FullMove},
-    endgame::{EndgameResult, EndgameSolver},
+    board::FullMove,
+    endgame::EndgameResult,
+    endgame_database::EndgameDatabase,
+    game_record::{GameRecord, RecordedMove, Verdict},
+    parallel_endgame::ParallelEndgameSolver,
     random::RandomGenerator,
-    solution_table::SolutionTable,
+    referee::Referee,
+    solution_estimator::estimate_solutions,
+    solution_table::{SolutionIds, SolutionTable, DEFAULT_CONTENT_ID_KEY},
+    symmetry::normalize_board,
 };
 
 #[derive(Debug, Parser)]
 struct Args {
+    /// CodeCup sqlite database.
+    #[arg(
+        short,
+        long,
+        required_unless_present = "records",
+        conflicts_with = "records"
+    )]
+    database: Option<PathBuf>,
+
+    /// Game records file, instead of the database.
+    #[arg(short, long)]
+    records: Option<PathBuf>,
+
+    /// Player to analyze: player ID in the database, or player name in the records.
     #[arg(short, long)]
-    database: PathBuf,
+    player: String,
 
+    /// Analyzed game records are written to this file.
     #[arg(short, long)]
-    player_id: u32,
+    output: Option<PathBuf>,
+
+    #[arg(short, long, default_value_t = 1)]
+    threads: usize,
+
+    /// Solved positions are loaded from and saved to this file.
+    #[arg(short, long)]
+    endgame_database: Option<PathBuf>,
 }
 
 const ENDGAME_MEMORY: usize = 512 << 20;
 const MAX_SOLUTIONS: u32 = 100_000;
+const ESTIMATE_PROBES: u32 = 10_000;
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-    let database = sqlite::open(args.database).unwrap();
-
-    let games = get_games(&database, args.player_id);
+    let games = match &args.database {
+        Some(path) => {
+            let database = sqlite::open(path)?;
+            get_games(&database, args.player.parse()?)
+        }
+        None => read_games(args.records.as_ref().unwrap(), &args.player)?,
+    };
+    let mut output = match &args.output {
+        Some(path) => Some(File::create(path)?),
+        None => None,
+    };
     let mut statistics = Statistics {
         num_games: 0,
         total_log_num_solutions: 0.0,
         total_log_difficulty: 0.0,
     };
-    let mut endgame_solver = EndgameSolver::new(ENDGAME_MEMORY);
-    let mut rng = RandomGenerator::with_nonce(0);
-    for game in games {
+    let mut endgame_solver = ParallelEndgameSolver::new(ENDGAME_MEMORY, args.threads);
+    let mut endgame_database = match &args.endgame_database {
+        Some(path) => {
+            let endgame_database = EndgameDatabase::load_or_new(path).unwrap();
+            eprintln!("Endgame database: {} positions", endgame_database.len());
+            Some(endgame_database)
+        }
+        None => None,
+    };
+    for mut game in games {
         analyze_game(
-            &game,
-            args.player_id,
+            &mut game,
+            &args.player,
             &mut endgame_solver,
-            &mut rng,
+            endgame_database.as_mut(),
             &mut statistics,
         );
+        if let Some(output) = &mut output {
+            writeln!(output, "{game}")?;
+        }
+        if let (Some(path), Some(endgame_database)) = (&args.endgame_database, &endgame_database) {
+            endgame_database.save(path).unwrap();
+        }
     }
     let avg_num_solutions =
         (statistics.total_log_num_solutions / statistics.num_games as f64).exp();
@@ -49,9 +102,19 @@ fn main() {
     println!(
         "Average mistake num_solutions: {avg_num_solutions:.3} difficulty: {avg_difficulty:.3}"
     );
+    Ok(())
+}
+
+/// Games of the player from a game records file.
+fn read_games(path: &Path, player: &str) -> Result<Vec<GameRecord>, Box<dyn Error>> {
+    let records = GameRecord::parse_all(&fs::read_to_string(path)?)?;
+    Ok(records
+        .into_iter()
+        .filter(|record| record.players.iter().any(|name| name == player))
+        .collect())
 }
 
-fn get_games(database: &sqlite::Connection, player_id: u32) -> Vec<Game> {
+fn get_games(database: &sqlite::Connection, player_id: u32) -> Vec<GameRecord> {
     let query = "
     SELECT round.competition_id, game.round_id, game.id, player_first, player_second, moves
     FROM game, round
@@ -70,69 +133,66 @@ fn get_games(database: &sqlite::Connection, player_id: u32) -> Vec<Game> {
         let player_first: u32 = statement.read::<i64, _>(3).unwrap().try_into().unwrap();
         let player_second: u32 = statement.read::<i64, _>(4).unwrap().try_into().unwrap();
         let moves: String = statement.read::<String, _>(5).unwrap();
-        games.push(Game {
-            competition_id,
-            round_id,
-            id: game_id,
-            player_ids: [player_first, player_second],
-            moves: moves
-                .split_whitespace()
-                .map(|s| s.parse::<FullMove>().unwrap())
-                .collect(),
-        });
+        let mut record = GameRecord::new([player_first.to_string(), player_second.to_string()]);
+        record.event = Some(format!(
+            "Competition {competition_id} round {round_id} game {game_id}"
+        ));
+        record.moves = moves
+            .split_whitespace()
+            .map(|s| RecordedMove::new(s.parse::<FullMove>().unwrap()))
+            .collect();
+        games.push(record);
     }
     games
 }
 
+/// Annotates the moves from the player's mistake to the end with verdicts.
 fn analyze_game(
-    game: &Game,
-    player_id: u32,
-    endgame_solver: &mut EndgameSolver,
-    rng: &mut RandomGenerator,
+    game: &mut GameRecord,
+    player: &str,
+    endgame_solver: &mut ParallelEndgameSolver,
+    mut endgame_database: Option<&mut EndgameDatabase>,
     statistics: &mut Statistics,
 ) {
     println!();
     println!(
-        "Competition {} round {} game {}",
-        game.competition_id, game.round_id, game.id
+        "{} vs {}{}",
+        game.players[0],
+        game.players[1],
+        match &game.event {
+            Some(event) => format!(" ({event})"),
+            None => String::new(),
+        }
     );
-    let mut boards = Vec::new();
-    let mut board = Board::new();
-    boards.push(board);
-    let mut mov_iter = game.moves.iter();
-    loop {
-        match mov_iter.next() {
-            None => {
-                println!("Game never finished");
-                return;
-            }
-            Some(FullMove::ClaimUnique) => {
-                boards.push(board);
-                break;
-            }
-            Some(FullMove::MoveClaimUnique(mov)) => {
-                board.make_move(*mov).unwrap();
-                boards.push(board);
-                break;
-            }
-            Some(FullMove::Move(mov)) => {
-                board.make_move(*mov).unwrap();
-                boards.push(board);
-            }
+    let mut referee = Referee::new();
+    let mut boards = vec![*referee.board()];
+    for mov in &game.moves {
+        if let Err(e) = referee.make_move(mov.mov) {
+            println!("Illegal move {}: {e}", mov.mov);
+            return;
+        }
+        boards.push(*referee.board());
+        if referee.result().is_some() {
+            break;
         }
     }
+    let Some(result) = referee.result() else {
+        println!("Game never finished");
+        return;
+    };
+    game.result.get_or_insert(result);
     #[derive(Debug, Copy, Clone)]
     struct StateAnalysis {
         num_solutions: u32,
         result: EndgameResult,
     }
     let mut state_analyses: Vec<Option<StateAnalysis>> = vec![None; boards.len()];
-    let (result, solutions) = SolutionTable::generate(
+    let (result, solutions) = SolutionTable::generate_with_ids(
         boards.last().unwrap(),
         0,
         2,
         Instant::now() + Duration::from_secs(3600),
-        rng,
+        SolutionIds::Content(DEFAULT_CONTENT_ID_KEY),
     );
     result.unwrap();
     assert!(!solutions.is_empty());
@@ -145,29 +205,42 @@ fn analyze_game(
         },
     });
 
-    for (i, mov) in game.moves.iter().enumerate().rev() {
+    for i in (0..boards.len() - 1).rev() {
+        let mov = game.moves[i].mov;
         eprintln!(
             "Move {} {} {}",
             i + 1,
             mov,
-            if game.player_ids[i % 2] == player_id {
+            if game.players[i % 2] == player {
                 "player"
             } else {
                 "opponent"
             }
         );
         eprintln!("Generating solutions");
-        let (result, solutions) = SolutionTable::generate(
-            &boards[i],
+        // Normalized, to share results with the engine and the endgame database.
+        let (result, solutions) = SolutionTable::generate_with_ids(
+            &normalize_board(&boards[i]).0,
             0,
             MAX_SOLUTIONS,
             Instant::now() + Duration::from_secs(3600),
-            rng,
+            SolutionIds::Content(DEFAULT_CONTENT_ID_KEY),
         );
         match result {
             Ok(()) => {}
             Err(e) => {
                 eprintln!("Can't generate solutions: {}", e);
+                let estimate = estimate_solutions(
+                    &boards[i],
+                    ESTIMATE_PROBES,
+                    &mut RandomGenerator::with_time_nonce(),
+                );
+                eprintln!(
+                    "Estimated solutions: {:.3e} [{:.3e}, {:.3e}]",
+                    estimate.estimate,
+                    estimate.lower(),
+                    estimate.upper()
+                );
                 statistics.num_games += 1;
                 statistics.total_log_num_solutions += (MAX_SOLUTIONS as f64).ln();
                 statistics.total_log_difficulty += (MAX_SOLUTIONS as f64).ln();
@@ -175,6 +248,11 @@ fn analyze_game(
             }
         }
         eprintln!("Analyzing endgame num_solutions = {}", solutions.len());
+        if let Some(endgame_database) = &endgame_database {
+            if endgame_database.preload(&solutions, &mut endgame_solver.transposition_table()) {
+                eprintln!("Found in endgame database");
+            }
+        }
         let start_time = Instant::now();
         let endgame_result = match endgame_solver.solve(
             &solutions,
@@ -195,11 +273,18 @@ fn analyze_game(
             "Result: {endgame_result:?} time={:.3?}",
             start_time.elapsed()
         );
+        if let Some(endgame_database) = &mut endgame_database {
+            endgame_database.insert(&solutions, endgame_result);
+        }
         state_analyses[i] = Some(StateAnalysis {
             num_solutions: solutions.len(),
             result: endgame_result,
         });
-        if game.player_ids[i % 2] == player_id
+        game.moves[i].verdict = Some(match endgame_result {
+            EndgameResult::Win(_) => Verdict::Win,
+            EndgameResult::Loss => Verdict::Loss,
+        });
+        if game.players[i % 2] == player
             && matches!(
                 state_analyses[i + 1].unwrap().result,
                 EndgameResult::Win { .. }
@@ -218,6 +303,9 @@ fn analyze_game(
                     ">>> MISTAKE at {} {mov} num_solutions: {num_solutions} difficulty: {difficulty}",
                     i + 1,
                 );
+                game.moves[i].comment = Some(format!(
+                    "mistake num_solutions: {num_solutions} difficulty: {difficulty}"
+                ));
                 statistics.num_games += 1;
                 statistics.total_log_num_solutions += (num_solutions.max(1) as f64).ln();
                 statistics.total_log_difficulty += (difficulty.max(1) as f64).ln();
@@ -228,15 +316,6 @@ fn analyze_game(
     panic!("Whole game analyzed");
 }
 
-#[derive(Debug)]
-struct Game {
-    competition_id: u32,
-    round_id: u32,
-    id: u32,
-    player_ids: [u32; 2],
-    moves: Vec<FullMove>,
-}
-
 #[derive(Debug)]
 struct Statistics {
     num_games: u32,
@@ -1,14 +1,16 @@
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser};
 use std::{
+    collections::HashSet,
     error::Error,
     fs::File,
     io::{BufWriter, Write},
     path::PathBuf,
 };
 use sudoku_game::{
-    board::{Board, Move},
+    board::{Board, FilledBoard, Move},
     digit::Digit,
     fast_solver::FastSolver,
+    grid_symmetry::canonical_grid,
     random::RandomGenerator,
     small::Small,
     solver::{Solver, SolverStep},
@@ -24,17 +26,37 @@ struct Args {
 
     #[arg(short, long)]
     output: PathBuf,
+
+    /// Skip sudokus whose solution is equivalent to that of an earlier one. Requires
+    /// `--max-solutions 1`: with more solutions the puzzle has no single grid to compare.
+    #[arg(long)]
+    distinct_grids: bool,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
+    if args.distinct_grids && args.max_solutions != 1 {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--distinct-grids requires --max-solutions 1",
+            )
+            .exit();
+    }
     let mut rng = RandomGenerator::with_nonce(10000);
     let output_file = File::create(args.output)?;
     let mut buf_writer = BufWriter::new(output_file);
-    for i in 0..args.num_sudokus {
+    let mut grids = HashSet::new();
+    let mut i = 0;
+    while i < args.num_sudokus {
         eprintln!("Generating sudoku {i}");
         let board = generate(args.max_solutions, &mut rng);
+        if args.distinct_grids && !grids.insert(canonical_grid(&solution(&board)).0) {
+            eprintln!("Duplicate grid");
+            continue;
+        }
         writeln!(buf_writer, "{}", board)?;
+        i += 1;
     }
     Ok(())
 }
@@ -81,3 +103,15 @@ fn count_solutions(board: &Board, max_solutions: u64) -> u64 {
     }
     num_solutions
 }
+
+/// The solution of a uniquely solvable board.
+fn solution(board: &Board) -> FilledBoard {
+    let mut solver = FastSolver::new(board);
+    loop {
+        match solver.step() {
+            SolverStep::Found(filled_board) => return filled_board,
+            SolverStep::NoProgress => {}
+            SolverStep::Done => panic!("No solutions"),
+        }
+    }
+}
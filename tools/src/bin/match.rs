@@ -1,19 +1,21 @@
 use clap::Parser;
 use std::{
+    collections::BTreeMap,
     error::Error,
-    fs::File,
-    io::{BufRead, BufReader, Write},
-    path::{Path, PathBuf},
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
     sync::{Arc, Mutex},
     thread::{self, JoinHandle},
-    time::{Duration, Instant},
+    time::Duration,
 };
-use subprocess::{Popen, PopenConfig, Redirection};
 use sudoku_game::{
-    board::{Board, FullMove, Move},
-    digit_set::DigitSet,
-    fast_solver::FastSolver,
-    solver::{Solver, SolverStep},
+    game_record::GameResult,
+    opening_suite::{self, Opening},
+};
+use tools::{
+    game_runner::{run_game, TimeControl, TimeControlArgs},
+    match_statistics::{MatchScore, Sprt, SprtResult},
 };
 
 #[derive(Debug, Parser)]
@@ -21,20 +23,54 @@ struct Args {
     #[arg(short, long, default_value_t = 8)]
     threads: u32,
 
+    /// Maximum number of game pairs, each player moving first once.
     #[arg(short, long)]
     games: u32,
 
-    player0: PathBuf,
-    player1: PathBuf,
+    /// Stop early when a sequential probability ratio test decides between Elo differences ELO0
+    /// and ELO1 of player 0.
+    #[arg(long, num_args = 2, value_names = ["ELO0", "ELO1"], allow_negative_numbers = true)]
+    sprt: Option<Vec<f64>>,
+
+    /// SPRT false positive rate.
+    #[arg(long, default_value_t = 0.05)]
+    alpha: f64,
+
+    /// SPRT false negative rate.
+    #[arg(long, default_value_t = 0.05)]
+    beta: f64,
+
+    /// Game records are written to this file.
+    #[arg(short, long)]
+    records: Option<PathBuf>,
+
+    /// Opening suite file. Game pair `n` starts from opening `n` modulo the number of openings.
+    #[arg(short, long)]
+    openings: Option<PathBuf>,
+
+    #[command(flatten)]
+    time_control: TimeControlArgs,
+
+    /// Command to run player 0: binary and arguments, e.g. "sudoku-game strategies=endgame=proof-number".
+    player0: String,
+    /// Command to run player 1.
+    player1: String,
 }
 
 struct MatchInfo {
     num_games: u32,
     games_played: u32,
-    player_binaries: [PathBuf; 2],
+    player_commands: [String; 2],
     wins: [u32; 2],
     fails: [u32; 2],
+    /// Failure reason -> count for each player.
+    failure_reasons: BTreeMap<String, [u32; 2]>,
+    sprt: Option<Sprt>,
+    sprt_result: Option<SprtResult>,
     max_time: [Duration; 2],
+    time_control: TimeControl,
+    openings: Vec<Opening>,
+    records: Option<File>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -42,10 +78,27 @@ fn main() -> Result<(), Box<dyn Error>> {
     let match_info = Arc::new(Mutex::new(MatchInfo {
         num_games: args.games,
         games_played: 0,
-        player_binaries: [args.player0, args.player1],
+        player_commands: [args.player0, args.player1],
         wins: [0, 0],
         fails: [0, 0],
+        failure_reasons: BTreeMap::new(),
+        sprt: args.sprt.map(|elos| Sprt {
+            elo0: elos[0],
+            elo1: elos[1],
+            alpha: args.alpha,
+            beta: args.beta,
+        }),
+        sprt_result: None,
         max_time: [Duration::ZERO, Duration::ZERO],
+        time_control: args.time_control.time_control(),
+        openings: match &args.openings {
+            Some(path) => opening_suite::parse_suite(&fs::read_to_string(path)?)?,
+            None => Vec::new(),
+        },
+        records: match &args.records {
+            Some(path) => Some(File::create(path)?),
+            None => None,
+        },
     }));
     let join_handles: Vec<JoinHandle<()>> = (0..args.threads)
         .map(|_| {
@@ -62,12 +115,31 @@ fn main() -> Result<(), Box<dyn Error>> {
         let match_info = match_info.lock().unwrap();
         println!(
             "{} vs {}",
-            match_info.player_binaries[0].display(),
-            match_info.player_binaries[1].display()
+            match_info.player_commands[0], match_info.player_commands[1]
         );
         println!("Wins: {} : {}", match_info.wins[0], match_info.wins[1]);
+        let score = match_info.score();
+        if let Some(elo) = score.elo() {
+            println!("Elo: {elo}");
+        }
+        if let Some(sprt) = &match_info.sprt {
+            let (lower, upper) = sprt.bounds();
+            println!(
+                "SPRT: elo0={} elo1={} LLR={:.2} [{lower:.2}, {upper:.2}] {}",
+                sprt.elo0,
+                sprt.elo1,
+                sprt.llr(&score),
+                match match_info.sprt_result {
+                    Some(result) => result.to_string(),
+                    None => "inconclusive".to_string(),
+                }
+            );
+        }
         if match_info.fails != [0, 0] {
             println!("Fails: {} : {}", match_info.fails[0], match_info.fails[1]);
+            for (reason, fails) in &match_info.failure_reasons {
+                println!("  {reason}: {} : {}", fails[0], fails[1]);
+            }
         }
         println!(
             "Time: {:.3?} : {:.3?}",
@@ -81,15 +153,22 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn run_matches(match_info: &Mutex<MatchInfo>) {
     loop {
         let game_number;
-        let player_binaries;
+        let player_commands;
+        let time_control;
+        let opening;
         {
             let mut match_info = match_info.lock().unwrap();
             game_number = match_info.games_played;
-            if game_number >= match_info.num_games {
+            if game_number >= match_info.num_games || match_info.sprt_result.is_some() {
                 break;
             }
             match_info.games_played += 1;
-            player_binaries = match_info.player_binaries.clone();
+            player_commands = match_info.player_commands.clone();
+            time_control = match_info.time_control;
+            opening = match match_info.openings.len() {
+                0 => Opening::default(),
+                len => match_info.openings[game_number as usize % len].clone(),
+            };
         }
 
         for side in 0..2 {
@@ -100,15 +179,32 @@ fn run_matches(match_info: &Mutex<MatchInfo>) {
                 format!("/tmp/player{}.{}.log", ["A", "B"][side ^ 1], game_name),
             ];
 
-            let (winner, failure, times) = run_match(
-                [&player_binaries[side], &player_binaries[side ^ 1]],
+            let (mut record, times) = run_game(
+                [&player_commands[side], &player_commands[side ^ 1]],
                 log_files,
+                time_control,
+                &opening,
             );
+            record.event = Some(format!("game {game_name}"));
+            let GameResult { winner, failure } = record.result.unwrap();
             let real_winner = winner ^ side;
             let mut match_info = match_info.lock().unwrap();
+            if let Some(records) = &mut match_info.records {
+                writeln!(records, "{record}").unwrap();
+            }
             match_info.wins[real_winner] += 1;
             if failure {
                 match_info.fails[real_winner ^ 1] += 1;
+                let reason = record
+                    .termination
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string());
+                match_info.failure_reasons.entry(reason).or_default()[real_winner ^ 1] += 1;
+            }
+            if let Some(sprt) = match_info.sprt {
+                if match_info.sprt_result.is_none() {
+                    match_info.sprt_result = sprt.result(&match_info.score());
+                }
             }
             for i in 0..2 {
                 match_info.max_time[i] = match_info.max_time[i].max(times[i ^ side]);
@@ -116,7 +212,11 @@ fn run_matches(match_info: &Mutex<MatchInfo>) {
             eprintln!(
                 "Game {game_name}: {}{} {} - {}",
                 ["A", "B"][real_winner],
-                if failure { " failure" } else { "" },
+                match (failure, &record.termination) {
+                    (false, _) => String::new(),
+                    (true, None) => " failure".to_string(),
+                    (true, Some(termination)) => format!(" failure ({termination})"),
+                },
                 match_info.wins[0],
                 match_info.wins[1],
             );
@@ -124,139 +224,12 @@ fn run_matches(match_info: &Mutex<MatchInfo>) {
     }
 }
 
-// Returns (winner, failure, times).
-fn run_match(
-    player_binaries: [&Path; 2],
-    log_files: [String; 2],
-    // game_name: &str,
-) -> (usize, bool, [Duration; 2]) {
-    let players: Vec<Popen> = (0..2)
-        .map(|i| {
-            let log_file = File::create(&log_files[i]).unwrap();
-            Popen::create(
-                &[player_binaries[i]],
-                PopenConfig {
-                    stdin: Redirection::Pipe,
-                    stdout: Redirection::Pipe,
-                    stderr: Redirection::File(log_file),
-                    ..Default::default()
-                },
-            )
-            .unwrap()
-        })
-        .collect();
-
-    let mut stdins: Vec<&File> = players.iter().map(|p| p.stdin.as_ref().unwrap()).collect();
-    let mut stdouts: Vec<BufReader<&File>> = players
-        .iter()
-        .map(|p| BufReader::new(p.stdout.as_ref().unwrap()))
-        .collect();
-
-    let winner;
-    let mut failure = false;
-    let mut times = [Duration::ZERO, Duration::ZERO];
-
-    let mut board = Board::new();
-    let mut turn = 0;
-    let mut prev_move: Option<Move> = None;
-
-    loop {
-        let start_time = Instant::now();
-        match prev_move {
-            None => writeln!(stdins[turn], "Start").unwrap(),
-            Some(prev_move) => writeln!(stdins[turn], "{prev_move}").unwrap(),
-        }
-        stdins[turn].flush().unwrap();
-
-        let mut line = String::new();
-        stdouts[turn].read_line(&mut line).unwrap();
-        times[turn] += start_time.elapsed();
-
-        let Ok(full_move) = line.trim().parse() else {
-            winner = Some(turn ^ 1);
-            failure = true;
-            break;
-        };
-        match full_move {
-            FullMove::ClaimUnique => {
-                if count_solutions(&board, 2, None) >= 2 {
-                    winner = Some(turn ^ 1);
-                    failure = true;
-                    break;
-                } else {
-                    winner = Some(turn);
-                    break;
-                }
-            }
-            FullMove::Move(mov) => {
-                if count_solutions(&board, 1, Some(mov)) == 0 {
-                    winner = Some(turn ^ 1);
-                    failure = true;
-                    break;
-                }
-                let Ok(()) = board.make_move(mov) else {
-                    winner = Some(turn ^ 1);
-                    failure = true;
-                    break;
-                };
-                if count_solutions(&board, 1, None) == 0 {
-                    winner = Some(turn ^ 1);
-                    failure = true;
-                    break;
-                }
-                prev_move = Some(mov);
-            }
-            FullMove::MoveClaimUnique(mov) => {
-                if count_solutions(&board, 1, Some(mov)) == 0 {
-                    winner = Some(turn ^ 1);
-                    failure = true;
-                    break;
-                }
-                let Ok(()) = board.make_move(mov) else {
-                    winner = Some(turn ^ 1);
-                    failure = true;
-                    break;
-                };
-                if count_solutions(&board, 2, None) == 1 {
-                    winner = Some(turn);
-                    break;
-                } else {
-                    winner = Some(turn ^ 1);
-                    failure = true;
-                    break;
-                }
-            }
-        }
-
-        turn ^= 1;
-    }
-
-    for mut stdin in stdins {
-        writeln!(stdin, "Quit").unwrap();
-        stdin.flush().unwrap();
-    }
-
-    for mut player in players {
-        assert!(player.wait().unwrap().success());
-    }
-
-    (winner.unwrap(), failure, times)
-}
-
-fn count_solutions(board: &Board, max_solutions: u32, except: Option<Move>) -> u32 {
-    let mut solutions = 0;
-    let mut solver = FastSolver::new(board);
-    if let Some(mov) = except {
-        solver.remove_possibilities(mov.square, DigitSet::only(mov.digit));
-    }
-    while solutions < max_solutions {
-        match solver.step() {
-            SolverStep::NoProgress => {}
-            SolverStep::Found(_) => {
-                solutions += 1;
-            }
-            SolverStep::Done => break,
+impl MatchInfo {
+    /// Score of player 0.
+    fn score(&self) -> MatchScore {
+        MatchScore {
+            wins: self.wins[0],
+            losses: self.wins[1],
         }
     }
-    solutions
 }
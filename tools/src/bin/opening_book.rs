@@ -0,0 +1,74 @@
+use clap::Parser;
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+};
+use sudoku_game::{game_record::GameRecord, opening_book::OpeningBook, referee::Referee};
+
+/// Builds an opening book from game records, e.g. from `self_play`, `match` or `tournament`.
+///
+/// Only move statistics are added. Verdicts in game records come from the endgame, long after
+/// `max_squares`, so book verdicts are left as they are.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Game records files.
+    #[arg(required = true)]
+    records: Vec<PathBuf>,
+
+    /// Moves are added while fewer squares than this are filled.
+    #[arg(long, default_value_t = 14)]
+    max_squares: u8,
+
+    /// Existing book to add the games to.
+    #[arg(short, long)]
+    book: Option<PathBuf>,
+
+    /// Output file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let mut book = match &args.book {
+        Some(path) => fs::read_to_string(path)?.parse()?,
+        None => OpeningBook::new(),
+    };
+    let mut num_games = 0;
+    for path in &args.records {
+        for record in GameRecord::parse_all(&fs::read_to_string(path)?)? {
+            // Failures say nothing about the position.
+            let Some(result) = record.result.filter(|result| !result.failure) else {
+                continue;
+            };
+            num_games += 1;
+            add_game(&mut book, &record, result.winner, args.max_squares);
+        }
+    }
+    eprintln!("{num_games} games, {} positions", book.len());
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    write!(output, "{book}")?;
+    Ok(())
+}
+
+fn add_game(book: &mut OpeningBook, record: &GameRecord, winner: usize, max_squares: u8) {
+    let mut referee = Referee::new();
+    for recorded_move in &record.moves {
+        let board = *referee.board();
+        if 81 - board.empty_squares().size() >= max_squares {
+            break;
+        }
+        let Some(mov) = recorded_move.mov.to_move() else {
+            break;
+        };
+        book.add_game_move(&board, mov, referee.turn() == winner);
+        if referee.make_move(recorded_move.mov).is_err() || referee.result().is_some() {
+            break;
+        }
+    }
+}
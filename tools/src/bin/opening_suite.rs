@@ -0,0 +1,137 @@
+use clap::Parser;
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::FullMove, midgame, opening_suite::Opening, player::Player, player_main::PlayerMain,
+    random::RandomGenerator, referee::Referee, settings::Settings, solution_table::SolutionTable,
+    strategy::Strategies,
+};
+
+/// Generates an opening suite of random legal move sequences.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Number of openings.
+    #[arg(short, long)]
+    count: u32,
+
+    /// Number of moves in each opening.
+    #[arg(short, long)]
+    moves: u32,
+
+    /// Solutions generated to choose random moves.
+    #[arg(long, default_value_t = 10_000)]
+    max_solutions: u32,
+
+    /// Self-play games from each opening. If non-zero, only balanced openings are kept.
+    #[arg(long, default_value_t = 0)]
+    balance_games: u32,
+
+    /// An opening is balanced if the player to move wins at least this fraction of games, and at
+    /// most 1 minus this fraction.
+    #[arg(long, default_value_t = 0.25)]
+    balance_margin: f64,
+
+    /// Settings of the self-play engine, e.g. "game_time_limit=1000".
+    #[arg(long, default_value = "")]
+    settings: String,
+
+    /// Output file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let mut settings = Settings::new();
+    settings.set_all(&args.settings)?;
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let mut rng = RandomGenerator::with_time_nonce();
+    writeln!(
+        output,
+        "# {} moves, balance games: {}",
+        args.moves, args.balance_games
+    )?;
+    let mut count = 0;
+    let mut attempts = 0;
+    while count < args.count {
+        attempts += 1;
+        let Some(opening) = random_opening(&mut rng, args.moves, args.max_solutions) else {
+            continue;
+        };
+        if args.balance_games != 0 {
+            let mover_wins = (0..args.balance_games)
+                .filter(|_| mover_wins_self_play(&settings, &opening))
+                .count();
+            let score = mover_wins as f64 / f64::from(args.balance_games);
+            eprintln!("Opening {opening}: mover score {score:.2}");
+            if score < args.balance_margin || score > 1.0 - args.balance_margin {
+                continue;
+            }
+        }
+        writeln!(output, "{opening}")?;
+        count += 1;
+    }
+    eprintln!("Generated {count} openings in {attempts} attempts");
+    Ok(())
+}
+
+/// None if the random moves end the game early.
+fn random_opening(
+    rng: &mut RandomGenerator,
+    num_moves: u32,
+    max_solutions: u32,
+) -> Option<Opening> {
+    let deadline = Instant::now() + Duration::from_secs(3600);
+    let mut referee = Referee::new();
+    let mut opening = Opening::default();
+    for _ in 0..num_moves {
+        // `generate_moves` fills in implied squares on the board, so use a copy.
+        let mut board = *referee.board();
+        let (_, solutions) = SolutionTable::generate(&board, 0, max_solutions, deadline, rng);
+        let moves = midgame::generate_moves(&mut board, &solutions, deadline);
+        if moves.is_empty() {
+            return None;
+        }
+        let mov = rng.choose(&moves).mov;
+        referee.make_move(FullMove::Move(mov)).unwrap();
+        if referee.result().is_some() {
+            return None;
+        }
+        opening.moves.push(mov);
+    }
+    Some(opening)
+}
+
+/// Plays a game from the opening. Returns whether the player to move after the opening wins.
+fn mover_wins_self_play(settings: &Settings, opening: &Opening) -> bool {
+    let mut referee = opening.referee().unwrap();
+    let mover = referee.turn();
+    let mut players = [0, 1].map(|_| {
+        let mut player = PlayerMain::with_strategies(settings.clone(), Strategies::new());
+        for &mov in &opening.moves {
+            player.opponent_move(mov);
+        }
+        player
+    });
+    let mut time_left = [settings.game_time_limit; 2];
+    while referee.result().is_none() {
+        let turn = referee.turn();
+        let start_time = Instant::now();
+        let fmov = players[turn].choose_move(start_time, time_left[turn]);
+        time_left[turn] = time_left[turn].saturating_sub(start_time.elapsed());
+        if referee.make_move(fmov).is_ok() && referee.result().is_none() {
+            if let FullMove::Move(mov) = fmov {
+                players[turn ^ 1].opponent_move(mov);
+            }
+        }
+    }
+    referee.result().unwrap().winner == mover
+}
@@ -60,7 +60,11 @@ fn opening_length(rng: &mut RandomGenerator, max_solutions: u32) -> u8 {
                 unreachable!();
             }
         }
-        let moves = midgame::generate_moves(&mut board, &solutions);
+        let moves = midgame::generate_moves(
+            &mut board,
+            &solutions,
+            Instant::now() + Duration::from_secs(3600),
+        );
         result = 81 - board.empty_squares().size();
         let mov = rng.choose(&moves).mov;
         board.make_move(mov).unwrap();
@@ -1,40 +1,218 @@
 use clap::Parser;
-use std::time::Instant;
-use sudoku_game::{board::FullMove, player::Player, player_main::PlayerMain, settings};
+use std::{
+    error::Error,
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+use sudoku_game::{
+    board::FullMove,
+    game_record::{GameRecord, RecordedMove},
+    player::Player,
+    player_main::PlayerMain,
+    referee::Referee,
+    settings::Settings,
+    strategy::{EndgameKind, Strategies},
+};
 
 #[derive(Debug, Parser)]
 struct Args {
+    /// Games played in parallel. Players in parallel games compete for the CPU, so comparisons
+    /// that depend on the time limits should use 1 thread, or cores reserved for each thread.
+    #[arg(short, long, default_value_t = 1)]
+    threads: u32,
+
+    /// Table memory in MiB, split between all players of all threads. Overrides
+    /// `transposition_table_memory`, and `proof_number_table_memory` for `endgame=proof-number`,
+    /// keeping their default ratio.
+    #[arg(long)]
+    memory: Option<usize>,
+
     #[arg(short, long)]
     games: u32,
+
+    /// Settings of player 0, e.g. "solutions_max=50000,strategies=endgame=proof-number".
+    #[arg(long, default_value = "")]
+    settings0: String,
+
+    /// Settings of player 1.
+    #[arg(long, default_value = "")]
+    settings1: String,
+
+    /// Game records are written to this file.
+    #[arg(short, long)]
+    records: Option<PathBuf>,
+}
+
+struct SelfPlayInfo {
+    num_games: u32,
+    games_played: u32,
+    settings: [Settings; 2],
+    names: [String; 2],
+    wins: [u32; 2],
+    records: Option<File>,
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-    let mut wins: [u32; 2] = [0, 0];
-    for game_num in 0..args.games {
-        eprintln!("Game {game_num} / {}", args.games);
-        let winner = play_game();
-        wins[winner] += 1;
+    let mut settings = [
+        parse_settings(&args.settings0)?,
+        parse_settings(&args.settings1)?,
+    ];
+    if let Some(memory) = args.memory {
+        for settings in &mut settings {
+            set_memory(
+                settings,
+                (memory << 20) / (2 * args.threads.max(1) as usize),
+            );
+        }
+    }
+    let self_play_info = Arc::new(Mutex::new(SelfPlayInfo {
+        num_games: args.games,
+        games_played: 0,
+        settings,
+        names: [player_name(&args.settings0), player_name(&args.settings1)],
+        wins: [0, 0],
+        records: match &args.records {
+            Some(path) => Some(File::create(path)?),
+            None => None,
+        },
+    }));
+    let join_handles: Vec<JoinHandle<()>> = (0..args.threads)
+        .map(|_| {
+            let self_play_info = self_play_info.clone();
+            thread::spawn(move || run_games(&self_play_info))
+        })
+        .collect();
+
+    for join_handle in join_handles {
+        join_handle.join().unwrap();
+    }
+
+    let self_play_info = self_play_info.lock().unwrap();
+    println!(
+        "Wins: {} : {}",
+        self_play_info.wins[0], self_play_info.wins[1]
+    );
+    Ok(())
+}
+
+/// Splits `memory` bytes between the tables the player uses.
+fn set_memory(settings: &mut Settings, memory: usize) {
+    if settings.strategies.endgame == EndgameKind::ProofNumber {
+        let defaults = Settings::new();
+        let total = defaults.transposition_table_memory + defaults.proof_number_table_memory;
+        settings.proof_number_table_memory =
+            (memory as f64 * defaults.proof_number_table_memory as f64 / total as f64) as usize;
+        settings.transposition_table_memory = memory - settings.proof_number_table_memory;
+    } else {
+        settings.transposition_table_memory = memory;
     }
-    println!("Wins: {} : {}", wins[0], wins[1]);
 }
 
-fn play_game() -> usize {
-    let mut players = [PlayerMain::new(), PlayerMain::new()];
-    let mut time_left = [settings::GAME_TIME_LIMIT; 2];
-    let mut turn = 0;
+/// Players alternate moving first.
+fn run_games(self_play_info: &Mutex<SelfPlayInfo>) {
     loop {
+        let game_num;
+        let settings;
+        let names;
+        {
+            let mut self_play_info = self_play_info.lock().unwrap();
+            game_num = self_play_info.games_played;
+            if game_num >= self_play_info.num_games {
+                break;
+            }
+            self_play_info.games_played += 1;
+            settings = self_play_info.settings.clone();
+            names = self_play_info.names.clone();
+        }
+        let side = (game_num % 2) as usize;
+        let [settings0, settings1] = settings;
+        let [strategies0, strategies1] =
+            [&settings0, &settings1].map(|settings| Strategies::from(settings.strategies));
+        let [name0, name1] = names;
+        let mut record = if side == 0 {
+            play_game(
+                [settings0, settings1],
+                [strategies0, strategies1],
+                [name0, name1],
+            )
+        } else {
+            play_game(
+                [settings1, settings0],
+                [strategies1, strategies0],
+                [name1, name0],
+            )
+        };
+        record.event = Some(format!("self-play game {game_num}"));
+        let real_winner = record.result.unwrap().winner ^ side;
+        let mut self_play_info = self_play_info.lock().unwrap();
+        self_play_info.wins[real_winner] += 1;
+        if let Some(records) = &mut self_play_info.records {
+            writeln!(records, "{record}").unwrap();
+        }
+        eprintln!(
+            "Game {game_num}: {real_winner} {} - {}",
+            self_play_info.wins[0], self_play_info.wins[1]
+        );
+    }
+}
+
+fn parse_settings(s: &str) -> Result<Settings, Box<dyn Error>> {
+    let mut settings = Settings::new();
+    settings.set_all(s)?;
+    Ok(settings)
+}
+
+/// The equivalent command line of the main binary.
+fn player_name(settings: &str) -> String {
+    let mut name = String::from("sudoku-game");
+    if !settings.is_empty() {
+        name += " ";
+        name += settings;
+    }
+    name
+}
+
+fn play_game(
+    settings: [Settings; 2],
+    strategies: [Strategies; 2],
+    players: [String; 2],
+) -> GameRecord {
+    let mut record = GameRecord::new(players);
+    let mut time_left = [settings[0].game_time_limit, settings[1].game_time_limit];
+    if time_left[0] == time_left[1] {
+        record.time_control = Some(time_left[0]);
+    }
+    let [settings0, settings1] = settings;
+    let [strategies0, strategies1] = strategies;
+    let mut players = [
+        PlayerMain::with_strategies(settings0, strategies0),
+        PlayerMain::with_strategies(settings1, strategies1),
+    ];
+    let mut referee = Referee::new();
+    while referee.result().is_none() {
+        let turn = referee.turn();
         let start_time = Instant::now();
         let fmov = players[turn].choose_move(start_time, time_left[turn]);
         let elapsed = start_time.elapsed();
         time_left[turn] = time_left[turn].saturating_sub(elapsed);
-        match fmov {
-            FullMove::Move(mov) => {
-                players[turn ^ 1].opponent_move(mov);
-                turn = 1 - turn;
-            }
-            FullMove::MoveClaimUnique(_) => return turn,
-            FullMove::ClaimUnique => return turn,
+        let mut recorded_move = RecordedMove {
+            time: Some(elapsed),
+            ..RecordedMove::new(fmov)
+        };
+        if let Err(e) = referee.make_move(fmov) {
+            recorded_move.comment = Some(format!("illegal: {e}"));
+            record.termination = Some(e.to_string());
+        }
+        record.moves.push(recorded_move);
+        if let (None, FullMove::Move(mov)) = (referee.result(), fmov) {
+            players[turn ^ 1].opponent_move(mov);
         }
     }
+    record.result = referee.result();
+    record
 }
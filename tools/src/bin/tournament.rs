@@ -0,0 +1,200 @@
+use clap::Parser;
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+use sudoku_game::{
+    game_record::GameResult,
+    opening_suite::{self, Opening},
+};
+use tools::{
+    game_runner::{run_game, TimeControl, TimeControlArgs},
+    match_statistics::elo_ratings,
+};
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(short, long, default_value_t = 8)]
+    threads: u32,
+
+    /// Number of game pairs between each two players, each player moving first once.
+    #[arg(short, long)]
+    games: u32,
+
+    /// Only the first player plays against each of the others.
+    #[arg(long)]
+    gauntlet: bool,
+
+    /// Game records are written to this file.
+    #[arg(short, long)]
+    records: Option<PathBuf>,
+
+    /// Opening suite file. Game pair `n` starts from opening `n` modulo the number of openings.
+    #[arg(short, long)]
+    openings: Option<PathBuf>,
+
+    #[command(flatten)]
+    time_control: TimeControlArgs,
+
+    /// Commands to run the players: binary and arguments, e.g. "sudoku-game solutions_max=50000".
+    #[arg(required = true, num_args = 2..)]
+    players: Vec<String>,
+}
+
+struct TournamentInfo {
+    player_commands: Vec<String>,
+    /// Game pairs to play: two player indices and the game number.
+    schedule: Vec<(usize, usize, u32)>,
+    games_started: usize,
+    /// `wins[i][j]`: wins of player `i` against player `j`.
+    wins: Vec<Vec<u32>>,
+    fails: Vec<u32>,
+    time_control: TimeControl,
+    openings: Vec<Opening>,
+    records: Option<File>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let n = args.players.len();
+    let mut schedule = Vec::new();
+    for game_number in 0..args.games {
+        for i in 0..n {
+            for j in i + 1..n {
+                if i == 0 || !args.gauntlet {
+                    schedule.push((i, j, game_number));
+                }
+            }
+        }
+    }
+    let tournament_info = Arc::new(Mutex::new(TournamentInfo {
+        player_commands: args.players,
+        schedule,
+        games_started: 0,
+        wins: vec![vec![0; n]; n],
+        fails: vec![0; n],
+        time_control: args.time_control.time_control(),
+        openings: match &args.openings {
+            Some(path) => opening_suite::parse_suite(&fs::read_to_string(path)?)?,
+            None => Vec::new(),
+        },
+        records: match &args.records {
+            Some(path) => Some(File::create(path)?),
+            None => None,
+        },
+    }));
+    let join_handles: Vec<JoinHandle<()>> = (0..args.threads)
+        .map(|_| {
+            let tournament_info = tournament_info.clone();
+            thread::spawn(move || run_games(&tournament_info))
+        })
+        .collect();
+
+    for join_handle in join_handles {
+        join_handle.join().unwrap();
+    }
+
+    print_cross_table(&tournament_info.lock().unwrap());
+    Ok(())
+}
+
+fn run_games(tournament_info: &Mutex<TournamentInfo>) {
+    loop {
+        let (i, j, game_number);
+        let player_commands;
+        let time_control;
+        let opening;
+        {
+            let mut tournament_info = tournament_info.lock().unwrap();
+            let Some(&game) = tournament_info.schedule.get(tournament_info.games_started) else {
+                break;
+            };
+            (i, j, game_number) = game;
+            tournament_info.games_started += 1;
+            player_commands = [
+                tournament_info.player_commands[i].clone(),
+                tournament_info.player_commands[j].clone(),
+            ];
+            time_control = tournament_info.time_control;
+            opening = match tournament_info.openings.len() {
+                0 => Opening::default(),
+                len => tournament_info.openings[game_number as usize % len].clone(),
+            };
+        }
+
+        let indices = [i, j];
+        for side in 0..2 {
+            let game_name = format!("{i}-{j}.{game_number}{}", ["a", "b"][side]);
+            let log_files = [
+                format!("/tmp/tournament.{game_name}.{}.log", indices[side]),
+                format!("/tmp/tournament.{game_name}.{}.log", indices[side ^ 1]),
+            ];
+
+            let (mut record, _) = run_game(
+                [&player_commands[side], &player_commands[side ^ 1]],
+                log_files,
+                time_control,
+                &opening,
+            );
+            record.event = Some(format!("tournament game {game_name}"));
+            let GameResult { winner, failure } = record.result.unwrap();
+            let (winner, loser) = (indices[winner ^ side], indices[winner ^ side ^ 1]);
+            let mut tournament_info = tournament_info.lock().unwrap();
+            if let Some(records) = &mut tournament_info.records {
+                writeln!(records, "{record}").unwrap();
+            }
+            tournament_info.wins[winner][loser] += 1;
+            if failure {
+                tournament_info.fails[loser] += 1;
+            }
+            eprintln!(
+                "Game {game_name}: {winner} beats {loser}{}",
+                match (failure, &record.termination) {
+                    (false, _) => String::new(),
+                    (true, None) => " (failure)".to_string(),
+                    (true, Some(termination)) => format!(" ({termination})"),
+                },
+            );
+        }
+    }
+}
+
+fn print_cross_table(tournament_info: &TournamentInfo) {
+    let wins = &tournament_info.wins;
+    let n = wins.len();
+    let elos = elo_ratings(wins);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| elos[b].total_cmp(&elos[a]));
+
+    for (i, command) in tournament_info.player_commands.iter().enumerate() {
+        println!("{i}: {command}");
+    }
+    println!();
+    print!("{:>3}", "");
+    for &j in &order {
+        print!(" {j:>9}");
+    }
+    println!(" {:>9} {:>7} {:>6}", "score", "elo", "fails");
+    for &i in &order {
+        print!("{i:>3}");
+        for &j in &order {
+            if i == j || wins[i][j] + wins[j][i] == 0 {
+                print!(" {:>9}", "-");
+            } else {
+                print!(" {:>9}", format!("{}-{}", wins[i][j], wins[j][i]));
+            }
+        }
+        let total_wins: u32 = wins[i].iter().sum();
+        let total_games: u32 = total_wins + (0..n).map(|j| wins[j][i]).sum::<u32>();
+        println!(
+            " {:>9} {:>+7.1} {:>6}",
+            format!("{total_wins}/{total_games}"),
+            elos[i],
+            tournament_info.fails[i]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use clap::Parser;
+use std::{
+    error::Error,
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use sudoku_game::{
+    board::{Board, Move},
+    error::ResourcesExceeded,
+    evaluation::{self, EvaluationModel, Features, NUM_FEATURES},
+    game_record::GameRecord,
+    midgame,
+    random::RandomGenerator,
+    referee::Referee,
+    settings::Settings,
+    solution_estimator::estimate_solutions,
+    solution_table::SolutionTable,
+};
+use tools::logistic_regression::{self, Sample};
+
+/// Fits the evaluation model to the outcomes of recorded games.
+///
+/// Samples are the positions after midgame moves, with the numbers of solutions that
+/// `midgame=evaluation` sees: lower bounds from `midgame::generate_moves` on up to
+/// `solutions_max` solutions of the position before the move, and estimates from
+/// `estimate_probes` random probes.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Game records files, e.g. from `self_play --records`.
+    #[arg(required = true)]
+    records: Vec<PathBuf>,
+
+    /// Solutions generated for each position, as in the `solutions_max` setting.
+    #[arg(long, default_value_t = Settings::new().solutions_max)]
+    solutions_max: u32,
+
+    /// Probes for the solution estimate, as in the `evaluation_estimate_probes` setting.
+    #[arg(long, default_value_t = Settings::new().evaluation_estimate_probes)]
+    estimate_probes: u32,
+
+    /// L2 regularization.
+    #[arg(long, default_value_t = 1e-3)]
+    l2: f64,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let mut rng = RandomGenerator::with_nonce(0);
+    let mut samples: Vec<Sample<NUM_FEATURES>> = Vec::new();
+    let mut num_games = 0;
+    for path in &args.records {
+        for record in GameRecord::parse_all(&fs::read_to_string(path)?)? {
+            let Some(result) = record.result else {
+                continue;
+            };
+            num_games += 1;
+            let mut referee = Referee::new();
+            for recorded_move in &record.moves {
+                if let Some(mov) = recorded_move.mov.to_move() {
+                    if let Some(features) = midgame_features(referee.board(), mov, &args, &mut rng)
+                    {
+                        // For the player to move after `mov`.
+                        samples.push((features, result.winner != referee.turn()));
+                    }
+                }
+                if referee.make_move(recorded_move.mov).is_err() || referee.result().is_some() {
+                    break;
+                }
+            }
+        }
+    }
+    eprintln!("{num_games} games, {} positions", samples.len());
+
+    let weights = logistic_regression::fit(&samples, args.l2);
+    let baseline = logistic_regression::log_loss(&[0.0; NUM_FEATURES], &samples);
+    let loss = logistic_regression::log_loss(&weights, &samples);
+    eprintln!("log loss {loss:.4} (baseline {baseline:.4})");
+    println!("evaluation_model={}", EvaluationModel { weights });
+    Ok(())
+}
+
+/// Features of the position after `mov`, if the midgame strategy would be used on `board` and
+/// would consider `mov`.
+fn midgame_features(
+    board: &Board,
+    mov: Move,
+    args: &Args,
+    rng: &mut RandomGenerator,
+) -> Option<Features> {
+    let deadline = Instant::now() + Duration::from_secs(3600);
+    let (res, solutions) = SolutionTable::generate(board, 0, args.solutions_max, deadline, rng);
+    if res != Err(ResourcesExceeded::Memory) {
+        return None;
+    }
+    let mut board = *board;
+    let moves = midgame::generate_moves(&mut board, &solutions, deadline);
+    let midgame_move = moves.iter().find(|m| m.mov == mov)?;
+    board.make_move(mov).unwrap();
+    let estimate = estimate_solutions(&board, args.estimate_probes, rng);
+    Some(evaluation::features(
+        &board,
+        f64::from(midgame_move.num_solutions_lower_bound),
+        estimate.estimate,
+    ))
+}
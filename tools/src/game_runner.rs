@@ -0,0 +1,285 @@
+//! Running games between player processes.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+use subprocess::{Popen, PopenConfig, Redirection};
+use sudoku_game::{
+    board::{FullMove, Move},
+    error::InvalidInput,
+    game_record::{GameRecord, RecordedMove},
+    opening_suite::Opening,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct TimeControl {
+    /// Total time per player per game.
+    pub game: Duration,
+    /// Time per move.
+    pub mov: Option<Duration>,
+}
+
+/// Time control command line arguments.
+#[derive(Debug, clap::Args)]
+pub struct TimeControlArgs {
+    /// Total time per player per game in milliseconds.
+    #[arg(long, default_value_t = 30_000)]
+    time_limit: u64,
+
+    /// Time per move in milliseconds.
+    #[arg(long)]
+    move_time_limit: Option<u64>,
+}
+
+impl TimeControlArgs {
+    pub fn time_control(&self) -> TimeControl {
+        TimeControl {
+            game: Duration::from_millis(self.time_limit),
+            mov: self.move_time_limit.map(Duration::from_millis),
+        }
+    }
+}
+
+/// Why a player lost without making a move.
+#[derive(Clone, Copy, Debug)]
+enum Failure {
+    Timeout,
+    MoveTimeout,
+    Crash,
+    InvalidOutput,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "timeout"),
+            Self::MoveTimeout => write!(f, "move timeout"),
+            Self::Crash => write!(f, "crash"),
+            Self::InvalidOutput => write!(f, "invalid output"),
+        }
+    }
+}
+
+/// How long a player has to exit after `Quit`.
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Splits a player command into arguments like a shell: arguments are separated by whitespace,
+/// and quotes and backslashes keep spaces in them, e.g. `"my player" --config 'a b.txt'`.
+pub fn split_command(command: &str) -> Result<Vec<String>, InvalidInput> {
+    let mut argv = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => argv.extend(arg.take()),
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next().ok_or(InvalidInput)? {
+                        '\'' => break,
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next().ok_or(InvalidInput)? {
+                        '"' => break,
+                        '\\' => arg.push(chars.next().ok_or(InvalidInput)?),
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '\\' => arg
+                .get_or_insert_with(String::new)
+                .push(chars.next().ok_or(InvalidInput)?),
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    argv.extend(arg);
+    if argv.is_empty() {
+        return Err(InvalidInput);
+    }
+    Ok(argv)
+}
+
+/// Plays a game, player 0 moving first. Returns the record and the total times.
+///
+/// Player commands are split by `split_command`. A non-empty `opening` is passed to the players
+/// with `--opening`. Player stderr goes to the log files. A player that fails to start loses by a
+/// crash on its first move.
+///
+/// # Panics
+///
+/// Panics if the opening is illegal.
+pub fn run_game(
+    player_commands: [&str; 2],
+    log_files: [String; 2],
+    time_control: TimeControl,
+    opening: &Opening,
+) -> (GameRecord, [Duration; 2]) {
+    let mut players = [0, 1].map(|i| {
+        let player = split_command(player_commands[i])
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|mut argv| {
+                if !opening.moves.is_empty() {
+                    argv.push("--opening".to_string());
+                    argv.push(opening.to_argument());
+                }
+                PlayerProcess::start(&argv, &log_files[i])
+            });
+        match player {
+            Ok(player) => Some(player),
+            Err(e) => {
+                eprintln!("Player {} failed to start: {e}", player_commands[i]);
+                None
+            }
+        }
+    });
+
+    let mut times = [Duration::ZERO, Duration::ZERO];
+    let mut record = GameRecord::new(player_commands.map(String::from));
+    record.time_control = Some(time_control.game);
+    let mut referee = opening.referee().unwrap();
+    for &mov in &opening.moves {
+        record.moves.push(RecordedMove {
+            comment: Some("opening".to_string()),
+            ..RecordedMove::new(FullMove::Move(mov))
+        });
+    }
+    let mut prev_move: Option<Move> = None;
+
+    while referee.result().is_none() {
+        let turn = referee.turn();
+        let start_time = Instant::now();
+        let time_left = time_control.game.saturating_sub(times[turn]);
+        let (timeout, timeout_failure) = match time_control.mov {
+            Some(move_limit) if move_limit < time_left => (move_limit, Failure::MoveTimeout),
+            _ => (time_left, Failure::Timeout),
+        };
+        let line = match &mut players[turn] {
+            Some(player) => match prev_move {
+                None => player.send("Start"),
+                Some(prev_move) => player.send(&prev_move.to_string()),
+            }
+            .map_err(|_| Failure::Crash)
+            .and_then(|()| player.receive(timeout, timeout_failure)),
+            None => Err(Failure::Crash),
+        };
+        let time = start_time.elapsed();
+        times[turn] += time;
+
+        let full_move = line.and_then(|line| {
+            if time > timeout {
+                return Err(timeout_failure);
+            }
+            line.trim().parse().map_err(|_| Failure::InvalidOutput)
+        });
+        let full_move = match full_move {
+            Ok(full_move) => full_move,
+            Err(failure) => {
+                referee.forfeit();
+                record.termination = Some(failure.to_string());
+                if let Some(player) = &mut players[turn] {
+                    player.kill();
+                }
+                break;
+            }
+        };
+        let mut recorded_move = RecordedMove {
+            time: Some(time),
+            ..RecordedMove::new(full_move)
+        };
+        if let Err(e) = referee.make_move(full_move) {
+            recorded_move.comment = Some(format!("illegal: {e}"));
+            record.termination = Some(e.to_string());
+        }
+        record.moves.push(recorded_move);
+        prev_move = full_move.to_move();
+    }
+
+    for (player, log_file) in players.into_iter().zip(log_files) {
+        let Some(player) = player else { continue };
+        if !player.quit() {
+            eprintln!("Player did not exit cleanly, see {log_file}");
+        }
+    }
+
+    record.result = referee.result();
+    (record, times)
+}
+
+/// A player process. Its output is read by a separate thread so that waiting for a move can time
+/// out.
+struct PlayerProcess {
+    process: Popen,
+    stdin: File,
+    lines: Receiver<String>,
+}
+
+impl PlayerProcess {
+    fn start(argv: &[String], log_file: &str) -> Result<Self, Box<dyn Error>> {
+        let log_file = File::create(log_file)?;
+        let mut process = Popen::create(
+            argv,
+            PopenConfig {
+                stdin: Redirection::Pipe,
+                stdout: Redirection::Pipe,
+                stderr: Redirection::File(log_file),
+                ..Default::default()
+            },
+        )?;
+        let stdin = process.stdin.take().unwrap();
+        let stdout = process.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            process,
+            stdin,
+            lines,
+        })
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{line}")?;
+        self.stdin.flush()
+    }
+
+    fn receive(&self, timeout: Duration, timeout_failure: Failure) -> Result<String, Failure> {
+        self.lines.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => timeout_failure,
+            RecvTimeoutError::Disconnected => Failure::Crash,
+        })
+    }
+
+    fn kill(&mut self) {
+        // Fails if the process has already exited.
+        _ = self.process.kill();
+    }
+
+    /// Asks the player to exit, and kills it if it doesn't. Returns whether it exited successfully.
+    fn quit(mut self) -> bool {
+        _ = self.send("Quit");
+        match self.process.wait_timeout(QUIT_TIMEOUT) {
+            Ok(Some(status)) => status.success(),
+            _ => {
+                self.kill();
+                _ = self.process.wait();
+                false
+            }
+        }
+    }
+}
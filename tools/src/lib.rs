@@ -0,0 +1,3 @@
+pub mod game_runner;
+pub mod logistic_regression;
+pub mod match_statistics;
@@ -0,0 +1,85 @@
+//! Logistic regression fitted by Newton's method.
+
+/// A feature vector and whether the outcome was positive.
+pub type Sample<const N: usize> = ([f64; N], bool);
+
+const MAX_ITERATIONS: usize = 100;
+const TOLERANCE: f64 = 1e-10;
+
+/// Probability of a positive outcome.
+pub fn predict<const N: usize>(weights: &[f64; N], features: &[f64; N]) -> f64 {
+    let x: f64 = weights.iter().zip(features).map(|(w, f)| w * f).sum();
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Average negative log likelihood.
+pub fn log_loss<const N: usize>(weights: &[f64; N], samples: &[Sample<N>]) -> f64 {
+    let total: f64 = samples
+        .iter()
+        .map(|(features, outcome)| {
+            let p = predict(weights, features);
+            -(if *outcome { p } else { 1.0 - p })
+                .max(f64::MIN_POSITIVE)
+                .ln()
+        })
+        .sum();
+    total / samples.len() as f64
+}
+
+/// Maximum likelihood weights with an L2 penalty `l2 * |weights|^2 / 2`.
+pub fn fit<const N: usize>(samples: &[Sample<N>], l2: f64) -> [f64; N] {
+    let mut weights = [0.0; N];
+    for _ in 0..MAX_ITERATIONS {
+        let mut gradient = weights.map(|w| l2 * w);
+        let mut hessian = [[0.0; N]; N];
+        for (i, row) in hessian.iter_mut().enumerate() {
+            row[i] = l2;
+        }
+        for (features, outcome) in samples {
+            let p = predict(&weights, features);
+            let error = p - if *outcome { 1.0 } else { 0.0 };
+            let curvature = p * (1.0 - p);
+            for i in 0..N {
+                gradient[i] += error * features[i];
+                for j in 0..N {
+                    hessian[i][j] += curvature * features[i] * features[j];
+                }
+            }
+        }
+        let step = solve_linear(hessian, gradient);
+        let mut step_size = 0.0;
+        for (w, s) in weights.iter_mut().zip(step) {
+            *w -= s;
+            step_size += s * s;
+        }
+        if step_size < TOLERANCE {
+            break;
+        }
+    }
+    weights
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting. `a` must be non-singular.
+fn solve_linear<const N: usize>(mut a: [[f64; N]; N], mut b: [f64; N]) -> [f64; N] {
+    for col in 0..N {
+        let pivot = (col..N)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .unwrap();
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..N {
+            let factor = a[row][col] / a[col][col];
+            let pivot_row = a[col];
+            for (x, p) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * p;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; N];
+    for row in (0..N).rev() {
+        let sum: f64 = (row + 1..N).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    x
+}
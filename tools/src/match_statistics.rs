@@ -0,0 +1,166 @@
+//! Statistics of match results: Elo estimates and the sequential probability ratio test (SPRT).
+//!
+//! Games can't be drawn, so a match is a sequence of Bernoulli trials.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Results from the point of view of one player.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub losses: u32,
+}
+
+/// Elo difference with a 95% confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EloEstimate {
+    pub elo: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Sequential probability ratio test of H0: elo = elo0 against H1: elo = elo1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Probability of accepting H1 if H0 is true.
+    pub alpha: f64,
+    /// Probability of accepting H0 if H1 is true.
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SprtResult {
+    AcceptH0,
+    AcceptH1,
+}
+
+/// Normal quantile for a 95% two-sided confidence interval.
+const Z_95: f64 = 1.959964;
+
+/// Expected score for an Elo difference.
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Elo difference for an expected score. Infinite for scores 0 and 1.
+pub fn score_to_elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Elo ratings of several players with mean 0, by maximum likelihood (Bradley-Terry model).
+///
+/// `wins[i][j]` is the number of wins of player `i` against player `j`. Each pair of players who
+/// played gets half a virtual win each, so that ratings are finite.
+pub fn elo_ratings(wins: &[Vec<u32>]) -> Vec<f64> {
+    const ITERATIONS: usize = 1000;
+    let n = wins.len();
+    let games = |i: usize, j: usize| f64::from(wins[i][j] + wins[j][i]);
+    let adjusted_wins = |i: usize, j: usize| {
+        if games(i, j) == 0.0 {
+            0.0
+        } else {
+            f64::from(wins[i][j]) + 0.5
+        }
+    };
+    let total_wins: Vec<f64> = (0..n)
+        .map(|i| (0..n).map(|j| adjusted_wins(i, j)).sum())
+        .collect();
+    // Minorization-maximization iterations on strengths 10^(elo/400).
+    let mut strengths = vec![1.0; n];
+    for _ in 0..ITERATIONS {
+        for i in 0..n {
+            let denominator: f64 = (0..n)
+                .filter(|&j| games(i, j) != 0.0)
+                .map(|j| (games(i, j) + 1.0) / (strengths[i] + strengths[j]))
+                .sum();
+            if denominator != 0.0 {
+                strengths[i] = total_wins[i] / denominator;
+            }
+        }
+    }
+    let elos: Vec<f64> = strengths.iter().map(|s| 400.0 * s.log10()).collect();
+    let mean = elos.iter().sum::<f64>() / n as f64;
+    elos.iter().map(|elo| elo - mean).collect()
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses
+    }
+
+    /// Fraction of games won.
+    pub fn score(&self) -> f64 {
+        f64::from(self.wins) / f64::from(self.games())
+    }
+
+    /// None if no games were played.
+    pub fn elo(&self) -> Option<EloEstimate> {
+        if self.games() == 0 {
+            return None;
+        }
+        // Wilson score interval.
+        let score = self.score();
+        let n = f64::from(self.games());
+        let z2 = Z_95 * Z_95;
+        let center = (score + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let radius =
+            Z_95 / (1.0 + z2 / n) * (score * (1.0 - score) / n + z2 / (4.0 * n * n)).sqrt();
+        Some(EloEstimate {
+            elo: score_to_elo(score),
+            lower: score_to_elo(center - radius),
+            upper: score_to_elo(center + radius),
+        })
+    }
+}
+
+impl Display for EloEstimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:+.1} [{:+.1}, {:+.1}]",
+            self.elo, self.lower, self.upper
+        )
+    }
+}
+
+impl Sprt {
+    /// Log likelihood ratio of H1 to H0.
+    pub fn llr(&self, score: &MatchScore) -> f64 {
+        let p0 = elo_to_score(self.elo0);
+        let p1 = elo_to_score(self.elo1);
+        f64::from(score.wins) * (p1 / p0).ln()
+            + f64::from(score.losses) * ((1.0 - p1) / (1.0 - p0)).ln()
+    }
+
+    /// The LLR bounds for accepting H0 and H1.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// None if the test should continue.
+    pub fn result(&self, score: &MatchScore) -> Option<SprtResult> {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            Some(SprtResult::AcceptH0)
+        } else if llr >= upper {
+            Some(SprtResult::AcceptH1)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for SprtResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AcceptH0 => write!(f, "H0 accepted"),
+            Self::AcceptH1 => write!(f, "H1 accepted"),
+        }
+    }
+}
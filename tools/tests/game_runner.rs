@@ -0,0 +1,48 @@
+use std::time::Duration;
+use sudoku_game::opening_suite::Opening;
+use tools::game_runner::{run_game, split_command, TimeControl};
+
+#[test]
+fn test_split_command() {
+    assert_eq!(
+        split_command("  player --x 1 ").unwrap(),
+        ["player", "--x", "1"]
+    );
+    assert_eq!(
+        split_command(r#""my player" --config 'a b.txt' c\ d e"f"'' """#).unwrap(),
+        ["my player", "--config", "a b.txt", "c d", "ef", ""]
+    );
+    assert_eq!(split_command(r#""a \"b\" \\""#).unwrap(), [r#"a "b" \"#]);
+    assert!(split_command("").is_err());
+    assert!(split_command("'unterminated").is_err());
+    assert!(split_command("\"unterminated").is_err());
+    assert!(split_command("trailing\\").is_err());
+}
+
+#[test]
+fn test_player_fails_to_start() {
+    let dir = std::env::temp_dir();
+    let log_files = ["a", "b"].map(|name| {
+        dir.join(format!(
+            "game_runner_test_{}_{name}.log",
+            std::process::id()
+        ))
+        .to_str()
+        .unwrap()
+        .to_string()
+    });
+    let (record, _) = run_game(
+        ["/nonexistent/player", "/nonexistent/player"],
+        log_files.clone(),
+        TimeControl {
+            game: Duration::from_secs(1),
+            mov: None,
+        },
+        &Opening::default(),
+    );
+    assert_eq!(record.termination.as_deref(), Some("crash"));
+    assert!(record.moves.is_empty());
+    for log_file in log_files {
+        let _ = std::fs::remove_file(log_file);
+    }
+}
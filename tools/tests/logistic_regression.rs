@@ -0,0 +1,21 @@
+use tools::logistic_regression::{fit, log_loss, predict, Sample};
+
+#[test]
+fn test_fit() {
+    // P(outcome) = 0.2 for x = 0, 0.8 for x = 1.
+    let mut samples: Vec<Sample<2>> = Vec::new();
+    for x in [0.0, 1.0] {
+        for i in 0..10 {
+            let outcome = if x == 0.0 { i < 2 } else { i < 8 };
+            samples.push(([1.0, x], outcome));
+        }
+    }
+    let weights = fit(&samples, 0.0);
+    assert!((predict(&weights, &[1.0, 0.0]) - 0.2).abs() < 1e-6);
+    assert!((predict(&weights, &[1.0, 1.0]) - 0.8).abs() < 1e-6);
+    assert!(log_loss(&weights, &samples) < log_loss(&[0.0, 0.0], &samples));
+
+    // Regularization shrinks the weights.
+    let shrunk = fit(&samples, 10.0);
+    assert!(shrunk[1].abs() < weights[1].abs());
+}
@@ -0,0 +1,68 @@
+use tools::match_statistics::{
+    elo_ratings, elo_to_score, score_to_elo, MatchScore, Sprt, SprtResult,
+};
+
+#[test]
+fn test_elo() {
+    assert_eq!(elo_to_score(0.0), 0.5);
+    assert!((score_to_elo(elo_to_score(100.0)) - 100.0).abs() < 1e-9);
+    assert!((score_to_elo(0.75) - 190.849).abs() < 1e-3);
+
+    assert_eq!(MatchScore::default().elo(), None);
+    let elo = MatchScore {
+        wins: 60,
+        losses: 40,
+    }
+    .elo()
+    .unwrap();
+    assert!((elo.elo - 70.437).abs() < 1e-3);
+    assert!(elo.lower > 0.0 && elo.lower < elo.elo && elo.upper > elo.elo);
+
+    let elo = MatchScore { wins: 5, losses: 0 }.elo().unwrap();
+    assert_eq!(elo.elo, f64::INFINITY);
+    assert!(elo.lower > 0.0 && elo.lower.is_finite());
+}
+
+#[test]
+fn test_sprt() {
+    let sprt = Sprt {
+        elo0: 0.0,
+        elo1: 50.0,
+        alpha: 0.05,
+        beta: 0.05,
+    };
+    let (lower, upper) = sprt.bounds();
+    assert!((upper - 2.944).abs() < 1e-3);
+    assert!((lower + 2.944).abs() < 1e-3);
+    assert_eq!(sprt.result(&MatchScore { wins: 6, losses: 4 }), None);
+    assert_eq!(
+        sprt.result(&MatchScore {
+            wins: 80,
+            losses: 20
+        }),
+        Some(SprtResult::AcceptH1)
+    );
+    assert_eq!(
+        sprt.result(&MatchScore {
+            wins: 200,
+            losses: 200
+        }),
+        Some(SprtResult::AcceptH0)
+    );
+}
+
+#[test]
+fn test_elo_ratings() {
+    // With the virtual half wins, 73.5 : 24.5 = 3 : 1.
+    let elos = elo_ratings(&[vec![0, 73], vec![24, 0]]);
+    assert!((elos[0] - elos[1] - 190.849).abs() < 1e-3);
+    assert!((elos[0] + elos[1]).abs() < 1e-9);
+
+    // Consistent results: 0 > 1 > 2.
+    let elos = elo_ratings(&[vec![0, 12, 20], vec![8, 0, 12], vec![4, 8, 0]]);
+    assert!(elos[0] > elos[1] && elos[1] > elos[2]);
+
+    // All wins, finite.
+    let elos = elo_ratings(&[vec![0, 10], vec![0, 0]]);
+    assert!(elos[0].is_finite() && elos[0] > 0.0);
+}